@@ -3,6 +3,7 @@ use alloc::string::String;
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Error {
     Network(String),
+    Url(String),
     Unexpected(String),
     InvalidUI(String),
     Other(String),
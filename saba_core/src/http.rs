@@ -1,7 +1,10 @@
 use alloc::{format, str};
+use core::fmt;
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 use crate::error::Error;
+use crate::inflate;
+use crate::url::Url;
 
 #[derive(Debug, Clone)]
 pub struct HttpResponse {
@@ -10,42 +13,133 @@ pub struct HttpResponse {
     reason: String,
     headers: Vec<Header>,
     body: String,
+    raw_body: Vec<u8>,
+    warnings: Vec<String>,
+    request_url: Option<Url>,
 }
 
 impl HttpResponse {
+    // 改行を\nに統一するのはステータスラインとヘッダのみで、ボディのバイト列はそのまま保つ
     pub fn new(raw_response: String) -> Result<Self, Error> {
-        // 前処理
-        let preprocessed_response = raw_response.trim_start().replace("\n\r", "\n");    // 最初の文字までの空白を削除し、改行を\nに統一
-        // ステータスラインを分割
-        let (status_line, remaining) = match preprocessed_response.split_once('\n') {
-            Some((s, r)) => (s, r),
-            None => return Err(Error::Network(format!("invalid http response: {}", preprocessed_response)))
+        return Self::from_bytes(raw_response.as_bytes());
+    }
+
+    // バイト列のレスポンスをパースする(gzipなどバイナリのボディをそのまま保つ)
+    pub fn from_bytes(raw_response: &[u8]) -> Result<Self, Error> {
+        // 最初の文字までの空白を削除
+        let start = raw_response.iter().position(|b| !b.is_ascii_whitespace()).unwrap_or(raw_response.len());
+        let raw_response = &raw_response[start..];
+        if raw_response.is_empty() {
+            return Err(Error::Network("empty response".to_string()));
+        }
+        // ステータスラインが改行で終わっていないレスポンスはエラー
+        if !raw_response.contains(&b'\n') {
+            return Err(Error::Network(format!("invalid http response: {}", String::from_utf8_lossy(raw_response))));
+        }
+        // ステータスラインとヘッダは最初の空行まで(\r\n\r\nもしくは\n\n)
+        let (head, body) = match find_header_end(raw_response) {
+            Some((end, separator_length)) => (&raw_response[..end], &raw_response[end + separator_length..]),
+            None => (raw_response, &raw_response[raw_response.len()..]),
         };
-        // ヘッダとボディを分割
-        let (headers, body) = match remaining.split_once("\n\n") {
-            Some((h, b)) => {
-                let mut headers = Vec::new();
-                for header in h.split('\n') {
-                    let splitted_header: Vec<&str> = header.splitn(2, ':').collect();
-                    headers.push(Header::new(
-                            String::from(splitted_header[0].trim()), 
-                            String::from(splitted_header[1].trim())
-                    ));
+        let head = String::from_utf8_lossy(head).replace("\r\n", "\n");
+        let (status_line, header_block) = match head.split_once('\n') {
+            Some((s, h)) => (s, Some(h)),
+            None => (head.as_str(), None),
+        };
+        return Self::from_parts(status_line, header_block, body);
+    }
+
+    // ステータスライン、ヘッダ部分、ボディからレスポンスを作る
+    fn from_parts(status_line: &str, header_block: Option<&str>, body: &[u8]) -> Result<Self, Error> {
+        let mut headers: Vec<Header> = Vec::new();
+        let mut warnings = Vec::new();
+        if let Some(h) = header_block {
+            for header in h.split('\n') {
+                // 空白かタブで始まる行は、前のヘッダの値が折り返されたもの(obs-fold)として1つの空白で繋げる
+                if header.starts_with([' ', '\t']) {
+                    if let Some(previous) = headers.last_mut() {
+                        previous.value = format!("{} {}", previous.value, header.trim());
+                        continue;
+                    }
+                }
+                // 空行は読み捨て、: のない行は警告を記録して読み捨てる
+                if header.trim().is_empty() {
+                    continue;
                 }
-                (headers, b)
+                let (name, value) = match header.split_once(':') {
+                    Some((name, value)) => (name, value),
+                    None => {
+                        warnings.push(format!("header line without a colon is ignored: {}", header.trim()));
+                        continue;
+                    }
+                };
+                headers.push(Header::new(String::from(name.trim()), String::from(value.trim())));
             }
-            None => (Vec::new(), remaining)
+        }
+
+        // Transfer-Encoding: chunkedとContent-Lengthが両方ある場合、Content-Lengthは無視しなければならない
+        // (リクエストスマグリングの兆候でもあるため警告として記録する)
+        let is_chunked = headers.iter().any(|h| {
+            h.name.eq_ignore_ascii_case("Transfer-Encoding") && h.value.to_ascii_lowercase().contains("chunked")
+        });
+        if is_chunked && headers.iter().any(|h| h.name.eq_ignore_ascii_case("Content-Length")) {
+            headers.retain(|h| !h.name.eq_ignore_ascii_case("Content-Length"));
+            warnings.push("Content-Length is ignored because Transfer-Encoding is chunked".to_string());
+        }
+        // chunkedの場合、ボディをデコードする(デコードできない場合は警告を記録し、そのまま残す)
+        let body = if is_chunked {
+            match decode_chunked(body) {
+                Ok(decoded) => decoded,
+                Err(e) => {
+                    warnings.push(e);
+                    body.to_vec()
+                }
+            }
+        } else {
+            body.to_vec()
+        };
+
+        // statusコードを取得(連続した空白で空の要素ができないようにsplit_whitespaceで分割)
+        let statuses: Vec<&str> = status_line.split_whitespace().collect();
+        // reasonは省略できるが(HTTP/1.1 200)、statusコードのないステータスラインはエラー
+        if statuses.len() < 2 {
+            return Err(Error::Network(format!("invalid status line: {}", status_line)));
+        }
+        let status_code = statuses[1].parse().unwrap_or(404);
+
+        // Content-Lengthがある場合、ボディをその長さ(バイト数)に揃える
+        // 1xx、204、304のレスポンスはボディを持たないため長さは検証しない
+        let body = match headers.iter().find(|h| h.name.eq_ignore_ascii_case("Content-Length")) {
+            Some(h) => {
+                let length: usize = match h.value.parse() {
+                    Ok(length) => length,
+                    Err(_) => return Err(Error::Network(format!("invalid Content-Length: {}", h.value))),
+                };
+                if (100..200).contains(&status_code) || status_code == 204 || status_code == 304 {
+                    body
+                } else if body.len() < length {
+                    return Err(Error::Network(format!(
+                        "body is shorter than Content-Length: expected {} bytes but received {} bytes",
+                        length,
+                        body.len()
+                    )));
+                } else {
+                    body[..length].to_vec()
+                }
+            }
+            None => body,
         };
 
-        // statusコードを取得
-        let statuses: Vec<&str> = status_line.split(' ').collect();
         // HttpResponse構造体
         Ok(Self {
             version: statuses[0].to_string(),
-            status_code: statuses[1].parse().unwrap_or(404),
-            reason: statuses[2].to_string(),
+            status_code,
+            reason: statuses[2..].join(" "),   // Not Foundのように複数の単語からなるreasonを結合する
             headers,
-            body: body.to_string(),
+            body: String::from_utf8_lossy(&body).to_string(),
+            raw_body: body,
+            warnings,
+            request_url: None,
         })
     }
 
@@ -65,14 +159,609 @@ impl HttpResponse {
     pub fn body(&self) -> String {
         return self.body.clone();
     }
+    // Content-Encodingを展開する前のボディのバイト列(Transfer-EncodingとContent-Lengthは処理済み)
+    pub fn raw_body(&self) -> Vec<u8> {
+        return self.raw_body.clone();
+    }
+    // 転送方法(Content-Length、chunked、接続が閉じられるまで)に関わらず、デコードしたボディのバイト数を返す
+    // Content-Encodingは展開する前のバイト数
+    pub fn effective_body_length(&self) -> usize {
+        return self.raw_body.len();
+    }
+    // Content-Encodingの符号化を小文字にして、適用された順(最後に適用された外側の符号化が末尾)に返す
+    // 複数のContent-Encodingヘッダがある場合は順に繋げ、何もしないことを表すidentityは含めない
+    pub fn content_encodings(&self) -> Vec<String> {
+        return self
+            .header_values("Content-Encoding")
+            .iter()
+            .flat_map(|value| value.split(','))
+            .map(|encoding| encoding.trim().to_ascii_lowercase())
+            .filter(|encoding| !encoding.is_empty() && encoding != "identity")
+            .collect();
+    }
+    // Content-Encoding(gzip、deflate)を展開したボディを返す
+    // 複数の符号化は最後に適用されたものから順に展開する
+    // 未知のContent-Encodingがある場合は、それより内側の符号化も展開できないため、その時点のボディをそのまま返す
+    pub fn decoded_body(&self) -> Result<String, Error> {
+        let mut body = self.raw_body.clone();
+        for encoding in self.content_encodings().iter().rev() {
+            body = match encoding.as_str() {
+                "gzip" | "x-gzip" => inflate::gunzip(&body)?,
+                // deflateはzlib形式のはずだが、zlibヘッダのないdeflate形式を送るサーバもある
+                "deflate" => match inflate::zlib_decompress(&body) {
+                    Ok(decoded) => decoded,
+                    Err(_) => inflate::inflate(&body)?,
+                },
+                _ => break,
+            };
+        }
+        return Ok(String::from_utf8_lossy(&body).to_string());
+    }
+    // ボディを行ごとに分割して返す(\r\nにも対応し、末尾の改行で空行は作らない)
+    pub fn body_lines(&self) -> Vec<String> {
+        return self.body.lines().map(|line| line.to_string()).collect();
+    }
+    // このレスポンスを返したリクエストのURLを設定する(相対的なLocationの解決に使う)
+    pub fn set_request_url(&mut self, url: Url) {
+        self.request_url = Some(url);
+    }
+    pub fn request_url(&self) -> Option<Url> {
+        return self.request_url.clone();
+    }
+    // ステータスコードを百の位で分類する
+    pub fn is_informational(&self) -> bool {
+        return (100..200).contains(&self.status_code);
+    }
+    pub fn is_success(&self) -> bool {
+        return (200..300).contains(&self.status_code);
+    }
+    pub fn is_redirect(&self) -> bool {
+        return (300..400).contains(&self.status_code);
+    }
+    pub fn is_client_error(&self) -> bool {
+        return (400..500).contains(&self.status_code);
+    }
+    pub fn is_server_error(&self) -> bool {
+        return (500..600).contains(&self.status_code);
+    }
+    // リダイレクトの場合、Locationヘッダのリダイレクト先を返す
+    // リクエストのURLが設定されていれば、相対的なLocationはそのURLを基準に解決する
+    pub fn location(&self) -> Option<Url> {
+        if !self.is_redirect() {
+            return None;
+        }
+        let location = self.header_value("Location").ok()?;
+        return match &self.request_url {
+            Some(base) => base.join(&location).ok(),
+            None => Url::new(location).parse().ok(),
+        };
+    }
+    // リダイレクトが、同じホストとパスのままhttpのURLをhttpsにするものかどうかを判定する
+    // 元のスキーマが分からないため、リクエストのURLが設定されていない場合はfalse
+    pub fn is_redirect_to_https(&self) -> bool {
+        let (request_url, location) = match (&self.request_url, self.location()) {
+            (Some(request_url), Some(location)) => (request_url, location),
+            _ => return false,
+        };
+        return request_url.scheme() == "http"
+            && location.scheme() == "https"
+            && request_url.host() == location.host()
+            && request_url.path() == location.path();
+    }
+    // パース中に検出した、エラーにはしない問題の一覧を返す
+    pub fn warnings(&self) -> Vec<String> {
+        return self.warnings.clone();
+    }
+    // 指定した名前(大文字小文字は区別しない)の最初のヘッダの値を返す
     pub fn header_value(&self, name: &str) -> Result<String, String> {
         for header in &self.headers {
-            if header.name == name {
+            if header.name.eq_ignore_ascii_case(name) {
                 return Ok(header.value.clone());
             }
         }
         return Err(format!("failed to find {} in headers", name));
     }
+    // 指定した名前(大文字小文字は区別しない)のヘッダの値をすべて返す(Set-Cookieのように複数回送られるヘッダ用)
+    pub fn header_values(&self, name: &str) -> Vec<String> {
+        return self
+            .headers
+            .iter()
+            .filter(|header| header.name.eq_ignore_ascii_case(name))
+            .map(|header| header.value.clone())
+            .collect();
+    }
+    // Varyヘッダに列挙されたリクエストヘッダ名を小文字に正規化して返す
+    // キャッシュはUrl::cache_keyに加えて、ここで返されたリクエストヘッダの値もキーに含める必要がある
+    // 複数のVaryヘッダがある場合は順に繋げる
+    pub fn vary_headers(&self) -> Vec<String> {
+        return self
+            .header_values("Vary")
+            .iter()
+            .flat_map(|value| value.split(','))
+            .map(|name| name.trim().to_ascii_lowercase())
+            .filter(|name| !name.is_empty())
+            .collect();
+    }
+    // ボディが接続が閉じられるまで続くか(Content-Lengthもchunkedもないか)を判定する
+    // 1xx、204、304のレスポンスはボディを持たないためfalse
+    pub fn body_is_until_close(&self) -> bool {
+        if self.is_informational() || self.status_code == 204 || self.status_code == 304 {
+            return false;
+        }
+        let is_chunked = self
+            .header_values("Transfer-Encoding")
+            .iter()
+            .any(|value| value.to_ascii_lowercase().contains("chunked"));
+        return !is_chunked && self.header_value("Content-Length").is_err();
+    }
+    // キャッシュしたレスポンスを再検証する条件付きGETのリクエストヘッダを返す
+    // ETagからIf-None-Matchを、Last-ModifiedからIf-Modified-Sinceを作る
+    pub fn conditional_request_headers(&self) -> Vec<(String, String)> {
+        let mut headers = Vec::new();
+        if let Ok(etag) = self.header_value("ETag") {
+            if !etag.is_empty() {
+                headers.push(("If-None-Match".to_string(), etag));
+            }
+        }
+        if let Ok(last_modified) = self.header_value("Last-Modified") {
+            if !last_modified.is_empty() {
+                headers.push(("If-Modified-Since".to_string(), last_modified));
+            }
+        }
+        return headers;
+    }
+    // Ageヘッダ(キャッシュに保存されてからの秒数)を返す
+    pub fn age(&self) -> Option<u64> {
+        return self.header_value("Age").ok()?.parse().ok();
+    }
+    // Cache-Controlヘッダのmax-ageディレクティブ(秒数)を返す
+    pub fn max_age(&self) -> Option<u64> {
+        let cache_control = self.header_value("Cache-Control").ok()?;
+        for directive in cache_control.split(',') {
+            if let Some((name, value)) = directive.split_once('=') {
+                if name.trim().eq_ignore_ascii_case("max-age") {
+                    return value.trim().trim_matches('"').parse().ok();
+                }
+            }
+        }
+        return None;
+    }
+    // レスポンスを受け取ってからnow_relative_secs秒経過した時点で、レスポンスが新鮮かどうかを返す
+    // max-ageが指定されていない場合は判定できないためNoneを返す
+    pub fn is_fresh(&self, now_relative_secs: u64) -> Option<bool> {
+        let max_age = self.max_age()?;
+        let current_age = self.age().unwrap_or(0).saturating_add(now_relative_secs);
+        return Some(current_age < max_age);
+    }
+    // 401レスポンスのWWW-Authenticateヘッダを認証チャレンジの一覧として返す
+    pub fn www_authenticate(&self) -> Option<Vec<AuthChallenge>> {
+        if self.status_code != 401 {
+            return None;
+        }
+        let value = self.header_value("WWW-Authenticate").ok()?;
+
+        let mut challenges: Vec<AuthChallenge> = Vec::new();
+        for item in split_outside_quotes(&value, ',') {
+            let item = item.trim();
+            if item.is_empty() {
+                continue;
+            }
+            // = より前に空白がある、もしくは = を含まない場合は新しいチャレンジの開始(先頭がスキーム)
+            let starts_challenge = match (item.find(char::is_whitespace), item.find('=')) {
+                (Some(space), Some(eq)) => space < eq,
+                (_, None) => true,
+                (None, Some(_)) => false,
+            };
+            let param = if starts_challenge {
+                let (scheme, rest) = item.split_once(char::is_whitespace).unwrap_or((item, ""));
+                challenges.push(AuthChallenge::new(scheme.to_string()));
+                rest.trim()
+            } else {
+                item
+            };
+            if let (Some(challenge), Some((name, value))) = (challenges.last_mut(), param.split_once('=')) {
+                let value = value.trim();
+                let value = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')).unwrap_or(value);
+                challenge.params.push((name.trim().to_ascii_lowercase(), value.to_string()));
+            }
+        }
+        return Some(challenges);
+    }
+    // Linkヘッダ(例: <https://cdn/app.js>; rel=preload; as=script)をbaseを基準に解決したURLとパラメータの一覧として返す
+    // 解決できないURLのエントリは読み捨てる
+    pub fn link_headers(&self, base: &Url) -> Vec<LinkHint> {
+        let mut hints = Vec::new();
+        for value in self.header_values("Link") {
+            for entry in split_outside_quotes(&value, ',') {
+                let entry = entry.trim();
+                let (reference, rest) = match entry.strip_prefix('<').and_then(|e| e.split_once('>')) {
+                    Some((r, rest)) => (r, rest),
+                    None => continue,
+                };
+                let url = match base.join(reference) {
+                    Ok(url) => url,
+                    Err(_) => continue,
+                };
+                let mut hint = LinkHint::new(url);
+                for param in split_outside_quotes(rest, ';') {
+                    let param = param.trim();
+                    if param.is_empty() {
+                        continue;
+                    }
+                    // 値を持たないパラメータは空文字列の値とする
+                    let (name, value) = param.split_once('=').unwrap_or((param, ""));
+                    let value = value.trim();
+                    let value = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')).unwrap_or(value);
+                    hint.params.push((name.trim().to_ascii_lowercase(), value.to_string()));
+                }
+                hints.push(hint);
+            }
+        }
+        return hints;
+    }
+    // すべてのSet-Cookieヘッダをパースしてクッキーの一覧として返す
+    // name=valueの形式になっていないSet-Cookieヘッダは読み捨てる
+    pub fn cookies(&self) -> Vec<Cookie> {
+        let mut cookies = Vec::new();
+        for value in self.header_values("Set-Cookie") {
+            let mut pairs = value.split(';');
+            // 最初のname=valueの組がクッキー本体
+            let (name, value) = match pairs.next().and_then(|pair| pair.split_once('=')) {
+                Some((n, v)) if !n.trim().is_empty() => (n.trim(), v.trim()),
+                _ => continue,
+            };
+            let mut cookie = Cookie::new(name.to_string(), value.to_string());
+            // 以降は属性(属性名は大文字小文字を区別せず、SecureとHttpOnlyは値を持たない)
+            for attribute in pairs {
+                let (attribute_name, attribute_value) = attribute.split_once('=').unwrap_or((attribute, ""));
+                let attribute_value = attribute_value.trim();
+                match attribute_name.trim().to_ascii_lowercase().as_str() {
+                    "domain" if !attribute_value.is_empty() => {
+                        // 先頭の . は無視する
+                        let domain = attribute_value.strip_prefix('.').unwrap_or(attribute_value);
+                        cookie.domain = Some(domain.to_ascii_lowercase());
+                    }
+                    // / から始まらないPathは指定されていないものとして扱う
+                    "path" if attribute_value.starts_with('/') => cookie.path = Some(attribute_value.to_string()),
+                    "expires" if !attribute_value.is_empty() => cookie.expires = Some(attribute_value.to_string()),
+                    "max-age" => {
+                        if let Ok(max_age) = attribute_value.parse() {
+                            cookie.max_age = Some(max_age);
+                        }
+                    }
+                    "secure" => cookie.secure = true,
+                    "httponly" => cookie.http_only = true,
+                    _ => {}
+                }
+            }
+            cookies.push(cookie);
+        }
+        return cookies;
+    }
+    // Content-Typeヘッダを小文字のメディアタイプとcharsetパラメータ(小文字)に分割して返す
+    pub fn content_type(&self) -> Option<(String, Option<String>)> {
+        let content_type = self.header_value("Content-Type").ok()?;
+        let mut params = content_type.split(';');
+        let mime = params.next()?.trim().to_ascii_lowercase();
+        let mut charset = None;
+        for param in params {
+            if let Some((name, value)) = param.split_once('=') {
+                if name.trim().eq_ignore_ascii_case("charset") {
+                    let value = value.trim().trim_matches('"');
+                    charset = Some(value.to_ascii_lowercase());
+                    break;
+                }
+            }
+        }
+        return Some((mime, charset));
+    }
+    // multipart/*のContent-Typeからboundaryパラメータを取り出す
+    pub fn multipart_boundary(&self) -> Option<String> {
+        let content_type = self.header_value("Content-Type").ok()?;
+        let mut params = content_type.split(';');
+        let mime = params.next()?.trim();
+        if !mime.to_ascii_lowercase().starts_with("multipart/") {
+            return None;
+        }
+        for param in params {
+            if let Some((name, value)) = param.split_once('=') {
+                if name.trim().eq_ignore_ascii_case("boundary") {
+                    // boundaryは " で囲まれている場合がある
+                    let value = value.trim();
+                    let value = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')).unwrap_or(value);
+                    return Some(value.to_string());
+                }
+            }
+        }
+        return None;
+    }
+    // 実際に扱うContent-Typeを返す
+    // Content-Typeがない、もしくはapplication/octet-streamの場合、ボディの先頭がHTMLであればtext/htmlとみなす
+    pub fn sniffed_content_type(&self) -> String {
+        let declared = self.header_value("Content-Type").ok();
+        let is_generic = match &declared {
+            Some(content_type) => {
+                let mime = content_type.split(';').next().unwrap_or("").trim();
+                mime.is_empty() || mime.eq_ignore_ascii_case("application/octet-stream")
+            }
+            None => true,
+        };
+        if is_generic {
+            let prefix: String = self.body.trim_start().chars().take(14).collect::<String>().to_ascii_lowercase();
+            if prefix.starts_with("<!doctype html") || prefix.starts_with("<html") {
+                return "text/html".to_string();
+            }
+        }
+        return declared.unwrap_or("application/octet-stream".to_string());
+    }
+}
+
+// ステータスラインとヘッダの終わりの空行の位置と、空行の区切りのバイト数を返す
+fn find_header_end(bytes: &[u8]) -> Option<(usize, usize)> {
+    for i in 0..bytes.len() {
+        if bytes[i..].starts_with(b"\r\n\r\n") {
+            return Some((i, 4));
+        }
+        if bytes[i..].starts_with(b"\n\n") {
+            return Some((i, 2));
+        }
+    }
+    return None;
+}
+
+// ボディの先頭のBOM(バイトオーダーマーク)から文字エンコーディングを判定する
+pub fn detect_bom_encoding(bytes: &[u8]) -> Option<String> {
+    if bytes.starts_with(&[0xEF, 0xBB, 0xBF]) {
+        return Some("UTF-8".to_string());
+    }
+    if bytes.starts_with(&[0xFF, 0xFE]) {
+        return Some("UTF-16LE".to_string());
+    }
+    if bytes.starts_with(&[0xFE, 0xFF]) {
+        return Some("UTF-16BE".to_string());
+    }
+    return None;
+}
+
+// chunked形式のボディ(16進数のチャンクサイズの行とデータの繰り返し)をデコードする
+// チャンクサイズの ; 以降の拡張は無視し、サイズ0のチャンクより後のトレーラーは読み捨てる
+fn decode_chunked(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let mut decoded = Vec::new();
+    let mut pos = 0;
+    while pos < bytes.len() {
+        // チャンクサイズの行を読む
+        let line_end = match bytes[pos..].iter().position(|&b| b == b'\n') {
+            Some(index) => pos + index,
+            None => bytes.len(),
+        };
+        let line = str::from_utf8(&bytes[pos..line_end]).unwrap_or("");
+        let size_str = line.split(';').next().unwrap_or("").trim();
+        let size = match usize::from_str_radix(size_str, 16) {
+            Ok(size) => size,
+            Err(_) => return Err(format!("invalid chunk size: {}", line.trim())),
+        };
+        // サイズ0のチャンクで終了
+        if size == 0 {
+            break;
+        }
+        // チャンクのデータを連結する
+        let start = line_end + 1;
+        if start + size > bytes.len() {
+            return Err("chunk data is shorter than its size".to_string());
+        }
+        decoded.extend_from_slice(&bytes[start..start + size]);
+        // データの後の改行を読み飛ばす
+        pos = start + size;
+        if bytes.get(pos) == Some(&b'\r') {
+            pos += 1;
+        }
+        if bytes.get(pos) == Some(&b'\n') {
+            pos += 1;
+        }
+    }
+    return Ok(decoded);
+}
+
+// " で囲まれた部分を除いて、区切り文字で文字列を分割する
+fn split_outside_quotes(s: &str, delimiter: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut in_quotes = false;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        if c == '"' {
+            in_quotes = !in_quotes;
+        } else if c == delimiter && !in_quotes {
+            parts.push(&s[start..i]);
+            start = i + c.len_utf8();
+        }
+    }
+    parts.push(&s[start..]);
+    return parts;
+}
+
+// WWW-Authenticateヘッダの認証チャレンジ(例: Basic realm="x")
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthChallenge {
+    scheme: String,
+    params: Vec<(String, String)>,
+}
+
+impl AuthChallenge {
+    pub fn new(scheme: String) -> Self {
+        Self { scheme, params: Vec::new() }
+    }
+
+    // ゲッター
+    pub fn scheme(&self) -> String {
+        return self.scheme.clone();
+    }
+    pub fn params(&self) -> Vec<(String, String)> {
+        return self.params.clone();
+    }
+    // パラメータ名(大文字小文字は区別しない)に対応する値を返す
+    pub fn param(&self, name: &str) -> Option<String> {
+        for (n, v) in &self.params {
+            if n.eq_ignore_ascii_case(name) {
+                return Some(v.clone());
+            }
+        }
+        return None;
+    }
+}
+
+// Set-Cookieヘッダで受け取ったクッキー
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cookie {
+    name: String,
+    value: String,
+    domain: Option<String>,
+    path: Option<String>,
+    expires: Option<String>,
+    max_age: Option<i64>,
+    secure: bool,
+    http_only: bool,
+}
+
+impl Cookie {
+    pub fn new(name: String, value: String) -> Self {
+        Self {
+            name,
+            value,
+            domain: None,
+            path: None,
+            expires: None,
+            max_age: None,
+            secure: false,
+            http_only: false,
+        }
+    }
+
+    // ゲッター
+    pub fn name(&self) -> String {
+        return self.name.clone();
+    }
+    pub fn value(&self) -> String {
+        return self.value.clone();
+    }
+    pub fn domain(&self) -> Option<String> {
+        return self.domain.clone();
+    }
+    pub fn path(&self) -> Option<String> {
+        return self.path.clone();
+    }
+    // Expires属性は日付の文字列のまま返す
+    pub fn expires(&self) -> Option<String> {
+        return self.expires.clone();
+    }
+    // Max-Age属性の秒数(0以下はすぐに期限切れになることを表す)
+    pub fn max_age(&self) -> Option<i64> {
+        return self.max_age;
+    }
+    pub fn secure(&self) -> bool {
+        return self.secure;
+    }
+    pub fn http_only(&self) -> bool {
+        return self.http_only;
+    }
+}
+
+// Linkヘッダの1つのエントリ(preloadやpreconnectのヒント)
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkHint {
+    url: Url,
+    params: Vec<(String, String)>,
+}
+
+impl LinkHint {
+    pub fn new(url: Url) -> Self {
+        Self { url, params: Vec::new() }
+    }
+
+    // ゲッター
+    pub fn url(&self) -> Url {
+        return self.url.clone();
+    }
+    pub fn params(&self) -> Vec<(String, String)> {
+        return self.params.clone();
+    }
+    // パラメータ名(大文字小文字は区別しない)に対応する値を返す
+    pub fn param(&self, name: &str) -> Option<String> {
+        for (n, v) in &self.params {
+            if n.eq_ignore_ascii_case(name) {
+                return Some(v.clone());
+            }
+        }
+        return None;
+    }
+    // relパラメータ(preload、preconnectなど)を返す
+    pub fn rel(&self) -> Option<String> {
+        return self.param("rel");
+    }
+}
+
+// 送信するHTTPリクエスト
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    method: String,
+    url: Url,
+    headers: Vec<Header>,
+    body: Option<String>,
+}
+
+impl HttpRequest {
+    pub fn new(method: &str, url: &Url) -> Self {
+        Self { method: method.to_string(), url: url.clone(), headers: Vec::new(), body: None }
+    }
+    // GETリクエストを作成する
+    pub fn get(url: &Url) -> Self {
+        return Self::new("GET", url);
+    }
+    // POSTリクエストを作成する
+    pub fn post(url: &Url) -> Self {
+        return Self::new("POST", url);
+    }
+    // ヘッダを追加する
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push(Header::new(name.to_string(), value.to_string()));
+        return self;
+    }
+    // ボディを設定する
+    pub fn body(mut self, body: String) -> Self {
+        self.body = Some(body);
+        return self;
+    }
+    // Hostヘッダの値(デフォルト以外のport番号はhostに続けて書く)を返す
+    fn host(&self) -> String {
+        if self.url.port() == self.url.default_port() {
+            return self.url.serialized_host();
+        }
+        return format!("{}:{}", self.url.serialized_host(), self.url.port());
+    }
+    // 送信するバイト列に変換する
+    pub fn into_bytes(self) -> Vec<u8> {
+        return self.to_string().into_bytes();
+    }
+}
+
+impl fmt::Display for HttpRequest {
+    // リクエストライン、ヘッダ、空行、ボディの順にHTTP/1.1の形式で書き出す
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} HTTP/1.1\r\n", self.method, self.url.encoded_request_target())?;
+        write!(f, "Host: {}\r\n", self.host())?;
+        for header in &self.headers {
+            write!(f, "{}: {}\r\n", header.name, header.value)?;
+        }
+        // Content-Lengthが指定されていない場合のみ、ボディの長さから補う
+        let has_content_length = self.headers.iter().any(|h| h.name.eq_ignore_ascii_case("Content-Length"));
+        if let Some(body) = self.body.as_ref().filter(|_| !has_content_length) {
+            write!(f, "Content-Length: {}\r\n", body.len())?;
+        }
+        write!(f, "\r\n")?;
+        if let Some(body) = &self.body {
+            write!(f, "{}", body)?;
+        }
+        return Ok(());
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -90,6 +779,7 @@ impl Header {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::vec;
 
     #[test]
     fn test_status_line_only() {
@@ -115,7 +805,7 @@ mod tests {
 
     #[test]
     fn test_two_headers_with_white_space() {
-        let raw = "HTTP/1.1 200 OK\nDate: xx xx xx\nContent-Length: 42\n\n".to_string();
+        let raw = "HTTP/1.1 200 OK\nDate: xx xx xx\nContent-Length: 0\n\n".to_string();
         let res = HttpResponse::new(raw).expect("failed to parse http response");
 
         assert_eq!(res.version(), "HTTP/1.1");
@@ -123,7 +813,17 @@ mod tests {
         assert_eq!(res.reason(), "OK");
 
         assert_eq!(res.header_value("Date"), Ok("xx xx xx".to_string()));
-        assert_eq!(res.header_value("Content-Length"), Ok("42".to_string()));
+        assert_eq!(res.header_value("Content-Length"), Ok("0".to_string()));
+    }
+
+    // 折り返された(継続行が空白かタブで始まる)ヘッダの場合
+    #[test]
+    fn test_folded_header() {
+        let raw = "HTTP/1.1 200 OK\r\nX-Long: first part,\r\n  second part,\r\n\tthird: part\r\nContent-Length: 0\r\n\r\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+
+        assert_eq!(res.header_value("X-Long"), Ok("first part, second part, third: part".to_string()));
+        assert_eq!(res.header_value("Content-Length"), Ok("0".to_string()));
     }
 
     #[test]
@@ -140,9 +840,742 @@ mod tests {
         assert_eq!(res.body(), "body message".to_string());
     }
 
+    // ステータスラインの区切りに連続した空白がある場合
+    #[test]
+    fn test_status_line_with_extra_spaces() {
+        let raw = "HTTP/1.1  200  OK\n\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+
+        assert_eq!(res.version(), "HTTP/1.1");
+        assert_eq!(res.status_code(), 200);
+        assert_eq!(res.reason(), "OK");
+    }
+
+    // reasonが省略されている場合
+    #[test]
+    fn test_status_line_without_reason() {
+        let raw = "HTTP/1.1 200\n\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+
+        assert_eq!(res.version(), "HTTP/1.1");
+        assert_eq!(res.status_code(), 200);
+        assert_eq!(res.reason(), "");
+    }
+
+    // statusコードがない場合
+    #[test]
+    fn test_status_line_without_status_code() {
+        let raw = "HTTP/1.1\n\n".to_string();
+        let expected = Err(Error::Network("invalid status line: HTTP/1.1".to_string()));
+        assert_eq!(HttpResponse::new(raw).map(|res| res.status_code()), expected);
+    }
+
+    // reasonが複数の単語からなる場合
+    #[test]
+    fn test_status_line_multi_word_reason() {
+        let raw = "HTTP/1.1 404 Not Found\n\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+
+        assert_eq!(res.version(), "HTTP/1.1");
+        assert_eq!(res.status_code(), 404);
+        assert_eq!(res.reason(), "Not Found");
+    }
+
+    // 末尾に改行のあるボディを行ごとに分割する場合
+    #[test]
+    fn test_body_lines_with_trailing_newline() {
+        let raw = "HTTP/1.1 200 OK\nContent-Type: text/plain\n\nline1\r\nline2\nline3\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+
+        assert_eq!(res.body_lines(), vec!["line1".to_string(), "line2".to_string(), "line3".to_string()]);
+    }
+
+    // 末尾に改行のないボディを行ごとに分割する場合
+    #[test]
+    fn test_body_lines_without_trailing_newline() {
+        let raw = "HTTP/1.1 200 OK\nContent-Type: text/plain\n\nline1\n\nline3".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+
+        assert_eq!(res.body_lines(), vec!["line1".to_string(), "".to_string(), "line3".to_string()]);
+    }
+
+    // Locationヘッダの値の前後に空白やタブがある場合
+    #[test]
+    fn test_location_with_surrounding_whitespace() {
+        let raw = "HTTP/1.1 302 Found\nLocation: \t http://example.com:8888/index.html \t\n\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+
+        let location = res.header_value("Location").expect("failed to find Location");
+        assert_eq!(location, "http://example.com:8888/index.html".to_string());
+
+        let url = Url::new(location).parse().expect("failed to parse Location");
+        assert_eq!(url.host(), "example.com".to_string());
+        assert_eq!(url.port(), "8888".to_string());
+        assert_eq!(url.path(), "index.html".to_string());
+    }
+
+    // ステータスコードを分類する場合
+    #[test]
+    fn test_status_classification() {
+        let res = HttpResponse::new("HTTP/1.1 101 Switching Protocols\n\n".to_string()).expect("failed to parse http response");
+        assert!(res.is_informational());
+        let res = HttpResponse::new("HTTP/1.1 204 No Content\n\n".to_string()).expect("failed to parse http response");
+        assert!(res.is_success() && !res.is_redirect());
+        let res = HttpResponse::new("HTTP/1.1 304 Not Modified\n\n".to_string()).expect("failed to parse http response");
+        assert!(res.is_redirect());
+        let res = HttpResponse::new("HTTP/1.1 404 Not Found\n\n".to_string()).expect("failed to parse http response");
+        assert!(res.is_client_error() && !res.is_server_error());
+        let res = HttpResponse::new("HTTP/1.1 503 Service Unavailable\n\n".to_string()).expect("failed to parse http response");
+        assert!(res.is_server_error());
+    }
+
+    // 301で絶対URLのLocationを持つ場合
+    #[test]
+    fn test_location_absolute() {
+        let raw = "HTTP/1.1 301 Moved Permanently\nLocation: http://example.org/new\n\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+
+        let expected = Url::new("http://example.org/new".to_string()).parse().expect("failed to parse url");
+        assert_eq!(res.location(), Some(expected));
+    }
+
+    // 302で相対URLのLocationを持つ場合、リクエストのURLを基準に解決する
+    #[test]
+    fn test_location_relative() {
+        let raw = "HTTP/1.1 302 Found\nLocation: ../login?next=1\n\n".to_string();
+        let mut res = HttpResponse::new(raw).expect("failed to parse http response");
+        res.set_request_url(Url::new("http://example.com/a/b/c".to_string()).parse().expect("failed to parse url"));
+
+        let expected = Url::new("http://example.com/a/login?next=1".to_string()).parse().expect("failed to parse url");
+        assert_eq!(res.location(), Some(expected));
+    }
+
+    // リダイレクト以外のレスポンスはLocationを持っていてもNoneを返す
+    #[test]
+    fn test_location_not_redirect() {
+        let raw = "HTTP/1.1 201 Created\nLocation: http://example.org/new\n\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+
+        assert_eq!(res.location(), None);
+    }
+
+    // 同じホストとパスのままhttpからhttpsにリダイレクトする場合
+    #[test]
+    fn test_is_redirect_to_https() {
+        let raw = "HTTP/1.1 301 Moved Permanently\nLocation: https://example.com/a/b\n\n".to_string();
+        let mut res = HttpResponse::new(raw).expect("failed to parse http response");
+        assert!(!res.is_redirect_to_https());
+        res.set_request_url(Url::new("http://example.com/a/b".to_string()).parse().expect("failed to parse url"));
+        assert!(res.is_redirect_to_https());
+    }
+
+    // スキーマが変わらない、もしくはホストが変わるリダイレクトの場合
+    #[test]
+    fn test_is_redirect_to_https_same_scheme() {
+        let raw = "HTTP/1.1 302 Found\nLocation: http://example.com/a/b\n\n".to_string();
+        let mut res = HttpResponse::new(raw).expect("failed to parse http response");
+        res.set_request_url(Url::new("http://example.com/a/b".to_string()).parse().expect("failed to parse url"));
+        assert!(!res.is_redirect_to_https());
+
+        let raw = "HTTP/1.1 302 Found\nLocation: https://example.org/a/b\n\n".to_string();
+        let mut res = HttpResponse::new(raw).expect("failed to parse http response");
+        res.set_request_url(Url::new("http://example.com/a/b".to_string()).parse().expect("failed to parse url"));
+        assert!(!res.is_redirect_to_https());
+    }
+
+    // リダイレクトでないレスポンスの場合
+    #[test]
+    fn test_is_redirect_to_https_not_redirect() {
+        let raw = "HTTP/1.1 200 OK\nLocation: https://example.com/a/b\n\n".to_string();
+        let mut res = HttpResponse::new(raw).expect("failed to parse http response");
+        res.set_request_url(Url::new("http://example.com/a/b".to_string()).parse().expect("failed to parse url"));
+        assert!(!res.is_redirect_to_https());
+    }
+
+    // Varyヘッダを正規化したヘッダ名に分割する場合
+    #[test]
+    fn test_vary_headers() {
+        let raw = "HTTP/1.1 200 OK\nVary: Accept-Encoding, User-Agent\n\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+
+        assert_eq!(res.vary_headers(), vec!["accept-encoding".to_string(), "user-agent".to_string()]);
+    }
+
+    // 複数のVaryヘッダがある場合
+    #[test]
+    fn test_multiple_vary_headers() {
+        let raw = "HTTP/1.1 200 OK\nVary: Accept-Encoding\nServer: x\nvary: Cookie, Accept-Language\n\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+
+        let expected = vec!["accept-encoding".to_string(), "cookie".to_string(), "accept-language".to_string()];
+        assert_eq!(res.vary_headers(), expected);
+    }
+
+    // Varyヘッダがない場合
+    #[test]
+    fn test_no_vary_header() {
+        let raw = "HTTP/1.1 200 OK\n\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+
+        assert!(res.vary_headers().is_empty());
+    }
+
+    // charsetを持たないContent-Typeの場合
+    #[test]
+    fn test_content_type_without_charset() {
+        let raw = "HTTP/1.1 200 OK\nContent-Type: text/html\n\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+
+        assert_eq!(res.content_type(), Some(("text/html".to_string(), None)));
+    }
+
+    // charsetを持つContent-Typeの場合
+    #[test]
+    fn test_content_type_with_charset() {
+        let raw = "HTTP/1.1 200 OK\nContent-Type:  Text/HTML ;  charset=UTF-8 \n\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+
+        assert_eq!(res.content_type(), Some(("text/html".to_string(), Some("utf-8".to_string()))));
+    }
+
+    // charset以外のパラメータも持つContent-Typeの場合、charsetのみを取り出す
+    #[test]
+    fn test_content_type_with_other_params() {
+        let raw = "HTTP/1.1 200 OK\nContent-Type: multipart/form-data; boundary=abc; charset=\"Shift_JIS\"\n\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+
+        assert_eq!(res.content_type(), Some(("multipart/form-data".to_string(), Some("shift_jis".to_string()))));
+    }
+
+    // Content-Typeがない場合
+    #[test]
+    fn test_content_type_absent() {
+        let raw = "HTTP/1.1 200 OK\nContent-Length: 0\n\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+
+        assert_eq!(res.content_type(), None);
+    }
+
+    // Content-Typeが宣言されている場合はそのまま使う
+    #[test]
+    fn test_sniffed_content_type_declared() {
+        let raw = "HTTP/1.1 200 OK\nContent-Type: text/plain; charset=utf-8\n\n<html></html>".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+
+        assert_eq!(res.sniffed_content_type(), "text/plain; charset=utf-8".to_string());
+    }
+
+    // Content-Typeがないかapplication/octet-streamで、ボディがHTMLの場合
+    #[test]
+    fn test_sniffed_content_type_html() {
+        let raw = "HTTP/1.1 200 OK\nServer: x\n\n  <!DOCTYPE html><html></html>".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+        assert_eq!(res.sniffed_content_type(), "text/html".to_string());
+
+        let raw = "HTTP/1.1 200 OK\nContent-Type: application/octet-stream\n\n<HTML><body></body></HTML>".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+        assert_eq!(res.sniffed_content_type(), "text/html".to_string());
+
+        let raw = "HTTP/1.1 200 OK\nServer: x\n\nhello".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+        assert_eq!(res.sniffed_content_type(), "application/octet-stream".to_string());
+    }
+
+    // boundaryが " で囲まれていない場合
+    #[test]
+    fn test_multipart_boundary() {
+        let raw = "HTTP/1.1 200 OK\nContent-Type: multipart/form-data; boundary=----abc\n\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+
+        assert_eq!(res.multipart_boundary(), Some("----abc".to_string()));
+    }
+
+    // boundaryが " で囲まれている場合
+    #[test]
+    fn test_quoted_multipart_boundary() {
+        let raw = "HTTP/1.1 200 OK\nContent-Type: multipart/mixed; charset=utf-8; boundary=\"a b:c\"\n\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+
+        assert_eq!(res.multipart_boundary(), Some("a b:c".to_string()));
+    }
+
+    // multipart以外のContent-Typeの場合
+    #[test]
+    fn test_non_multipart_boundary() {
+        let raw = "HTTP/1.1 200 OK\nContent-Type: text/html; boundary=abc\n\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+
+        assert_eq!(res.multipart_boundary(), None);
+    }
+
+    // Transfer-Encoding: chunkedとContent-Lengthが両方ある場合
+    #[test]
+    fn test_chunked_with_content_length() {
+        let raw = "HTTP/1.1 200 OK\nTransfer-Encoding: chunked\nContent-Length: 1000\n\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+
+        assert!(res.header_value("Content-Length").is_err());
+        assert_eq!(res.header_value("Transfer-Encoding"), Ok("chunked".to_string()));
+        assert_eq!(res.warnings().len(), 1);
+    }
+
+    // GETリクエストを書き出す場合
+    #[test]
+    fn test_get_request() {
+        let url = Url::new("http://example.com/index.html?a=1".to_string()).parse().expect("failed to parse url");
+        let request = HttpRequest::get(&url).header("Accept", "text/html");
+
+        assert_eq!(
+            request.to_string(),
+            "GET /index.html?a=1 HTTP/1.1\r\nHost: example.com\r\nAccept: text/html\r\n\r\n"
+        );
+    }
+
+    // pathが空でport番号を指定したGETリクエストを書き出す場合
+    #[test]
+    fn test_get_request_empty_path() {
+        let url = Url::new("http://example.com:8080".to_string()).parse().expect("failed to parse url");
+        let request = HttpRequest::get(&url);
+
+        assert_eq!(request.into_bytes(), b"GET / HTTP/1.1\r\nHost: example.com:8080\r\n\r\n".to_vec());
+    }
+
+    // ボディを持つPOSTリクエストを書き出す場合
+    #[test]
+    fn test_post_request() {
+        let url = Url::new("https://example.com/form".to_string()).parse().expect("failed to parse url");
+        let request = HttpRequest::post(&url)
+            .header("Content-Type", "text/plain")
+            .body("hello".to_string());
+
+        assert_eq!(
+            request.into_bytes(),
+            b"POST /form HTTP/1.1\r\nHost: example.com\r\nContent-Type: text/plain\r\nContent-Length: 5\r\n\r\nhello".to_vec()
+        );
+    }
+
+    // Content-Lengthを指定したPOSTリクエストでは、Content-Lengthを補わない場合
+    #[test]
+    fn test_post_request_with_content_length() {
+        let url = Url::new("http://example.com/form".to_string()).parse().expect("failed to parse url");
+        let request = HttpRequest::post(&url)
+            .header("content-length", "5")
+            .body("hello".to_string());
+
+        assert_eq!(
+            request.into_bytes(),
+            b"POST /form HTTP/1.1\r\nHost: example.com\r\ncontent-length: 5\r\n\r\nhello".to_vec()
+        );
+    }
+
+    // BOMから文字エンコーディングを判定する場合
+    #[test]
+    fn test_detect_bom_encoding() {
+        assert_eq!(detect_bom_encoding(&[0xEF, 0xBB, 0xBF, b'a']), Some("UTF-8".to_string()));
+        assert_eq!(detect_bom_encoding(&[0xFF, 0xFE, b'a', 0x00]), Some("UTF-16LE".to_string()));
+        assert_eq!(detect_bom_encoding(&[0xFE, 0xFF, 0x00, b'a']), Some("UTF-16BE".to_string()));
+    }
+
+    // BOMがない場合
+    #[test]
+    fn test_detect_bom_encoding_none() {
+        assert_eq!(detect_bom_encoding(b"<html>"), None);
+        assert_eq!(detect_bom_encoding(&[0xFF]), None);
+        assert_eq!(detect_bom_encoding(&[]), None);
+    }
+
+    // 同じ名前のヘッダが複数ある場合
+    #[test]
+    fn test_header_values() {
+        let raw = "HTTP/1.1 200 OK\nSet-Cookie: a=1\nContent-Length: 0\nSet-Cookie: b=2\n\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+
+        assert_eq!(res.header_values("Set-Cookie"), vec!["a=1".to_string(), "b=2".to_string()]);
+        assert_eq!(res.header_value("Set-Cookie"), Ok("a=1".to_string()));
+        assert!(res.header_values("Location").is_empty());
+    }
+
+    // ヘッダ名の大文字小文字を区別しない場合
+    #[test]
+    fn test_header_value_case_insensitive() {
+        let raw = "HTTP/1.1 200 OK\nContent-Length: 5\nset-cookie: a=1\n\nhello".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+
+        assert_eq!(res.header_value("content-length"), Ok("5".to_string()));
+        assert_eq!(res.header_values("SET-COOKIE"), vec!["a=1".to_string()]);
+    }
+
+    // chunked形式のボディをデコードする場合
+    #[test]
+    fn test_chunked_body() {
+        let raw = "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n7;name=value\r\n, world\r\n0\r\nExpires: never\r\n\r\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+
+        assert_eq!(res.body(), "hello, world");
+        assert!(res.warnings().is_empty());
+    }
+
+    // Content-Lengthのボディとchunkedのボディの長さ
+    #[test]
+    fn test_effective_body_length() {
+        let res = HttpResponse::new("HTTP/1.1 200 OK\nContent-Length: 5\n\nhello, world".to_string()).expect("failed to parse http response");
+        assert_eq!(res.effective_body_length(), 5);
+
+        let raw = "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n7\r\n, world\r\n0\r\n\r\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+        assert_eq!(res.effective_body_length(), 12);
+
+        let res = HttpResponse::new("HTTP/1.0 200 OK\nServer: x\n\nあ".to_string()).expect("failed to parse http response");
+        assert_eq!(res.effective_body_length(), 3);
+    }
+
+    // チャンクサイズの行に拡張がある場合
+    #[test]
+    fn test_chunked_body_with_extension() {
+        let raw = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5;foo=bar\r\nhello\r\n0\r\n\r\n";
+        let res = HttpResponse::from_bytes(raw).expect("failed to parse http response");
+
+        assert_eq!(res.body(), "hello");
+        assert_eq!(res.raw_body(), b"hello".to_vec());
+        assert!(res.warnings().is_empty());
+    }
+
+    // ボディに\r\nを含む場合も、ボディは書き換えずにContent-Lengthと照合する
+    #[test]
+    fn test_crlf_in_body() {
+        let raw = "HTTP/1.1 200 OK\r\nContent-Length: 6\r\n\r\na\r\n\r\nb".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+        assert_eq!(res.body(), "a\r\n\r\nb".to_string());
+        assert_eq!(res.header_value("Content-Length"), Ok("6".to_string()));
+    }
+
+    // ヘッダのない、空行で終わらないレスポンスの場合
+    #[test]
+    fn test_response_without_headers() {
+        let res = HttpResponse::from_bytes(b"HTTP/1.1 200 OK\n").expect("failed to parse http response");
+        assert_eq!(res.status_code(), 200);
+        assert!(res.headers().is_empty());
+
+        let res = HttpResponse::from_bytes(b"HTTP/1.1 204 No Content\r\n").expect("failed to parse http response");
+        assert_eq!(res.status_code(), 204);
+        assert_eq!(res.reason(), "No Content".to_string());
+        assert!(res.headers().is_empty());
+        assert!(res.warnings().is_empty());
+    }
+
+    // : のないヘッダの行は警告を記録して読み捨てる
+    #[test]
+    fn test_header_line_without_colon() {
+        let res = HttpResponse::from_bytes(b"HTTP/1.1 200 OK\r\nbroken\r\nServer: x\r\n\r\n").expect("failed to parse http response");
+        assert_eq!(res.header_value("Server"), Ok("x".to_string()));
+        assert_eq!(res.headers().len(), 1);
+        assert_eq!(res.warnings().len(), 1);
+    }
+
+    // チャンクサイズが不正な場合は警告を記録し、ボディをそのまま残す
+    #[test]
+    fn test_invalid_chunked_body() {
+        let raw = "HTTP/1.1 200 OK\nTransfer-Encoding: chunked\n\nxyz\nhello\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+
+        assert_eq!(res.body(), "xyz\nhello\n");
+        assert_eq!(res.warnings().len(), 1);
+    }
+
+    // Content-Lengthもchunkedもないレスポンスのボディは接続が閉じられるまで続く
+    #[test]
+    fn test_body_is_until_close() {
+        let res = HttpResponse::new("HTTP/1.0 200 OK\nServer: x\n\nbody".to_string()).expect("failed to parse http response");
+        assert!(res.body_is_until_close());
+
+        let res = HttpResponse::new("HTTP/1.1 200 OK\nContent-Length: 4\n\nbody".to_string()).expect("failed to parse http response");
+        assert!(!res.body_is_until_close());
+    }
+
+    // chunkedのレスポンスとボディを持たないレスポンスの場合
+    #[test]
+    fn test_body_is_until_close_chunked_and_no_content() {
+        let res = HttpResponse::new("HTTP/1.1 200 OK\nTransfer-Encoding: chunked\n\n0\n\n".to_string()).expect("failed to parse http response");
+        assert!(!res.body_is_until_close());
+
+        let res = HttpResponse::new("HTTP/1.0 204 No Content\n\n".to_string()).expect("failed to parse http response");
+        assert!(!res.body_is_until_close());
+    }
+
+    // ETagとLast-Modifiedの両方がある場合
+    #[test]
+    fn test_conditional_request_headers() {
+        let raw = "HTTP/1.1 200 OK\nETag: \"abc\"\nLast-Modified: Wed, 21 Oct 2015 07:28:00 GMT\n\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+        let expected = vec![
+            ("If-None-Match".to_string(), "\"abc\"".to_string()),
+            ("If-Modified-Since".to_string(), "Wed, 21 Oct 2015 07:28:00 GMT".to_string()),
+        ];
+        assert_eq!(res.conditional_request_headers(), expected);
+
+        // どちらもない場合は空
+        let res = HttpResponse::new("HTTP/1.1 200 OK\n\n".to_string()).expect("failed to parse http response");
+        assert!(res.conditional_request_headers().is_empty());
+    }
+
+    // Linkヘッダに2つのエントリがある場合
+    #[test]
+    fn test_link_headers() {
+        let raw = "HTTP/1.1 200 OK\nLink: <https://cdn.example.com/app.js>; rel=preload; as=script, </style.css>; rel=\"preload\"; as=style\n\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+        let base = Url::new("http://example.com/index.html".to_string()).parse().expect("failed to parse url");
+        let hints = res.link_headers(&base);
+
+        assert_eq!(hints.len(), 2);
+        assert_eq!(hints[0].url().host(), "cdn.example.com".to_string());
+        assert_eq!(hints[0].url().path(), "app.js".to_string());
+        assert_eq!(hints[0].rel(), Some("preload".to_string()));
+        assert_eq!(hints[0].param("AS"), Some("script".to_string()));
+        assert_eq!(hints[1].url(), Url::new("http://example.com/style.css".to_string()).parse().expect("failed to parse url"));
+        assert_eq!(hints[1].params(), vec![("rel".to_string(), "preload".to_string()), ("as".to_string(), "style".to_string())]);
+    }
+
+    // 属性を持たないSet-Cookieヘッダの場合
+    #[test]
+    fn test_cookies_minimal() {
+        let raw = "HTTP/1.1 200 OK\nSet-Cookie: id=abc\n\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+
+        assert_eq!(res.cookies(), vec![Cookie::new("id".to_string(), "abc".to_string())]);
+    }
+
+    // Path、Secure、Max-Ageなどの属性を持つSet-Cookieヘッダが複数ある場合
+    #[test]
+    fn test_cookies_with_attributes() {
+        let raw = "HTTP/1.1 200 OK\nSet-Cookie: session=xyz; path=/app; SECURE; Max-Age=3600; HttpOnly; Domain=.Example.com\nSet-Cookie: invalid\nSet-Cookie: lang=ja; Max-Age=abc\n\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+        let cookies = res.cookies();
+
+        assert_eq!(cookies.len(), 2);
+        assert_eq!(cookies[0].name(), "session".to_string());
+        assert_eq!(cookies[0].value(), "xyz".to_string());
+        assert_eq!(cookies[0].path(), Some("/app".to_string()));
+        assert_eq!(cookies[0].domain(), Some("example.com".to_string()));
+        assert_eq!(cookies[0].max_age(), Some(3600));
+        assert!(cookies[0].secure());
+        assert!(cookies[0].http_only());
+        assert_eq!(cookies[0].expires(), None);
+        assert_eq!(cookies[1], Cookie::new("lang".to_string(), "ja".to_string()));
+    }
+
+    // gzipで圧縮されたボディを展開する場合
+    #[test]
+    fn test_decoded_body_gzip() {
+        let mut raw = b"HTTP/1.1 200 OK\r\nContent-Encoding: gzip\r\nContent-Length: 33\r\n\r\n".to_vec();
+        raw.extend_from_slice(&[
+            0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xcb, 0x48, 0xcd, 0xc9, 0xc9, 0xd7, 0x51, 0x28,
+            0xcf, 0x2f, 0xca, 0x49, 0xe1, 0x02, 0x00, 0x53, 0x74, 0x24, 0xf4, 0x0d, 0x00, 0x00, 0x00,
+        ]);
+        let res = HttpResponse::from_bytes(&raw).expect("failed to parse http response");
+
+        assert_eq!(res.status_code(), 200);
+        assert_eq!(res.raw_body().len(), 33);
+        assert_eq!(res.decoded_body(), Ok("hello, world\n".to_string()));
+    }
+
+    // 複数のContent-Encodingを適用された順に返す場合
+    #[test]
+    fn test_content_encodings() {
+        let raw = "HTTP/1.1 200 OK\nContent-Encoding: gzip, BR\n\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+        assert_eq!(res.content_encodings(), vec!["gzip".to_string(), "br".to_string()]);
+
+        let raw = "HTTP/1.1 200 OK\nContent-Encoding: identity\nContent-Encoding: deflate,,gzip\n\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+        assert_eq!(res.content_encodings(), vec!["deflate".to_string(), "gzip".to_string()]);
+
+        let raw = "HTTP/1.1 200 OK\n\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+        assert!(res.content_encodings().is_empty());
+    }
+
+    // deflateの後にgzipで圧縮されたボディを、gzip、deflateの順に展開する場合
+    #[test]
+    fn test_decoded_body_stacked_encodings() {
+        let mut raw = b"HTTP/1.1 200 OK\r\nContent-Encoding: deflate, gzip\r\n\r\n".to_vec();
+        raw.extend_from_slice(&[
+            0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xab, 0x98, 0xa3, 0xad, 0xe7, 0xe9, 0x73, 0xce,
+            0xcf, 0x97, 0x91, 0x81, 0xfb, 0x08, 0xd3, 0x03, 0x00, 0xe5, 0x9d, 0x0f, 0x2c, 0x0f, 0x00, 0x00, 0x00,
+        ]);
+        let res = HttpResponse::from_bytes(&raw).expect("failed to parse http response");
+
+        assert_eq!(res.decoded_body(), Ok("stacked".to_string()));
+    }
+
+    // 途中で終わっているgzipのボディはエラーになる場合
+    #[test]
+    fn test_decoded_body_truncated_gzip() {
+        let mut raw = b"HTTP/1.1 200 OK\nContent-Encoding: gzip\n\n".to_vec();
+        raw.extend_from_slice(&[0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xcb, 0x48]);
+        let res = HttpResponse::from_bytes(&raw).expect("failed to parse http response");
+
+        assert!(res.decoded_body().is_err());
+    }
+
+    // 未知のContent-Encodingの場合はボディをそのまま返す
+    #[test]
+    fn test_decoded_body_unknown_encoding() {
+        let raw = "HTTP/1.1 200 OK\nContent-Encoding: br2000\n\nbody".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+
+        assert_eq!(res.decoded_body(), Ok("body".to_string()));
+        assert_eq!(res.body(), "body".to_string());
+    }
+
+    // 外側の符号化が未知の場合は、内側のgzipも展開せずにそのまま返す
+    #[test]
+    fn test_decoded_body_unknown_outer_encoding() {
+        let mut raw = b"HTTP/1.1 200 OK\r\nContent-Encoding: gzip, br\r\n\r\n".to_vec();
+        let gzip_body = [
+            0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xcb, 0x48, 0xcd, 0xc9, 0xc9, 0xd7, 0x51, 0x28,
+            0xcf, 0x2f, 0xca, 0x49, 0xe1, 0x02, 0x00, 0x53, 0x74, 0x24, 0xf4, 0x0d, 0x00, 0x00, 0x00,
+        ];
+        raw.extend_from_slice(&gzip_body);
+        let res = HttpResponse::from_bytes(&raw).expect("failed to parse http response");
+
+        assert_eq!(res.decoded_body(), Ok(String::from_utf8_lossy(&gzip_body).to_string()));
+    }
+
+    // ボディの長さがContent-Lengthと一致する場合
+    #[test]
+    fn test_content_length_exact() {
+        let raw = "HTTP/1.1 200 OK\nContent-Length: 10\n\n0123456789".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+
+        assert_eq!(res.body(), "0123456789".to_string());
+    }
+
+    // ボディがContent-Lengthより長い場合は切り詰める
+    #[test]
+    fn test_content_length_truncates_body() {
+        let raw = "HTTP/1.1 200 OK\nContent-Length: 4\n\n0123456789".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+
+        assert_eq!(res.body(), "0123".to_string());
+    }
+
+    // ボディがContent-Lengthより短い場合や、Content-Lengthが数値でない場合はエラーになる
+    #[test]
+    fn test_content_length_errors() {
+        let raw = "HTTP/1.1 200 OK\nContent-Length: 10\n\n0123".to_string();
+        assert_eq!(
+            HttpResponse::new(raw).map(|res| res.body()),
+            Err(Error::Network("body is shorter than Content-Length: expected 10 bytes but received 4 bytes".to_string()))
+        );
+
+        let raw = "HTTP/1.1 200 OK\nContent-Length: ten\n\n0123".to_string();
+        assert_eq!(
+            HttpResponse::new(raw).map(|res| res.body()),
+            Err(Error::Network("invalid Content-Length: ten".to_string()))
+        );
+    }
+
+    // ボディを持たない304レスポンスでは長さを検証しない
+    #[test]
+    fn test_content_length_not_modified() {
+        let raw = "HTTP/1.1 304 Not Modified\nContent-Length: 100\n\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+
+        assert_eq!(res.body(), "".to_string());
+    }
+
+    // Content-Lengthのみの場合は警告を記録しない
+    #[test]
+    fn test_content_length_only() {
+        let raw = "HTTP/1.1 200 OK\nContent-Length: 4\n\nbody".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+
+        assert_eq!(res.header_value("Content-Length"), Ok("4".to_string()));
+        assert!(res.warnings().is_empty());
+    }
+
+    // max-ageに対してAgeと経過時間の合計が小さい場合
+    #[test]
+    fn test_fresh_response() {
+        let raw = "HTTP/1.1 200 OK\nCache-Control: public, max-age=100\nAge: 10\n\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+
+        assert_eq!(res.age(), Some(10));
+        assert_eq!(res.max_age(), Some(100));
+        assert_eq!(res.is_fresh(20), Some(true));
+    }
+
+    // max-ageに対してAgeと経過時間の合計が大きい場合
+    #[test]
+    fn test_stale_response() {
+        let raw = "HTTP/1.1 200 OK\nCache-Control: max-age=60\nAge: 50\n\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+
+        assert_eq!(res.is_fresh(20), Some(false));
+    }
+
+    // max-ageがない場合は判定できない
+    #[test]
+    fn test_freshness_without_max_age() {
+        let raw = "HTTP/1.1 200 OK\nAge: 50\n\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+
+        assert_eq!(res.is_fresh(0), None);
+    }
+
+    // Basic認証のチャレンジの場合
+    #[test]
+    fn test_www_authenticate_basic() {
+        let raw = "HTTP/1.1 401 Unauthorized\nWWW-Authenticate: Basic realm=\"Secure Area\"\n\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+
+        let challenges = res.www_authenticate().expect("failed to parse WWW-Authenticate");
+        assert_eq!(challenges.len(), 1);
+        assert_eq!(challenges[0].scheme(), "Basic".to_string());
+        assert_eq!(challenges[0].param("realm"), Some("Secure Area".to_string()));
+    }
+
+    // 複数のパラメータを持つDigest認証のチャレンジの場合
+    #[test]
+    fn test_www_authenticate_digest() {
+        let raw = "HTTP/1.1 401 Unauthorized\nWWW-Authenticate: Digest realm=\"a, b\", qop=\"auth\", nonce=abc123\n\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+
+        let challenges = res.www_authenticate().expect("failed to parse WWW-Authenticate");
+        assert_eq!(challenges.len(), 1);
+        assert_eq!(challenges[0].scheme(), "Digest".to_string());
+        assert_eq!(challenges[0].params(), vec![
+            ("realm".to_string(), "a, b".to_string()),
+            ("qop".to_string(), "auth".to_string()),
+            ("nonce".to_string(), "abc123".to_string()),
+        ]);
+    }
+
+    // ステータスコードが401でない場合
+    #[test]
+    fn test_www_authenticate_not_401() {
+        let raw = "HTTP/1.1 200 OK\nWWW-Authenticate: Basic realm=\"x\"\n\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse http response");
+
+        assert_eq!(res.www_authenticate(), None);
+    }
+
+    // 空のレスポンスの場合
+    #[test]
+    fn test_empty_response() {
+        assert_eq!(HttpResponse::new("".to_string()).unwrap_err(), Error::Network("empty response".to_string()));
+    }
+
+    // 空白のみのレスポンスの場合
+    #[test]
+    fn test_whitespace_only_response() {
+        assert_eq!(HttpResponse::new("   \n".to_string()).unwrap_err(), Error::Network("empty response".to_string()));
+    }
+
     #[test]
     fn test_invalid() {
         let raw = "HTTP/1.1 200 OK".to_string();
         assert!(HttpResponse::new(raw).is_err());
+        assert!(HttpResponse::from_bytes(b"HTTP/1.1 200 OK").is_err());
     }
 }
@@ -0,0 +1,413 @@
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use crate::error::Error;
+
+// 長さの符号(257〜285)に対応する基本の長さと追加ビット数
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+// 距離の符号(0〜29)に対応する基本の距離と追加ビット数
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073,
+    4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13,
+];
+// 動的ハフマン符号で、符号長の符号長が並ぶ順番
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+// 展開後のデータの最大バイト数
+// 数百バイトの圧縮データが数ギガバイトに展開されてメモリを使い果たさないように、超えた場合はエラーにする
+pub const MAX_OUTPUT_LENGTH: usize = 64 * 1024 * 1024;
+
+// 途中で入力が終わった場合のエラー
+fn truncated() -> Error {
+    return Error::Network("compressed data is truncated".to_string());
+}
+
+// 展開後のデータが最大バイト数を超える場合のエラー
+fn too_large() -> Error {
+    return Error::Network("decompressed data is too large".to_string());
+}
+
+// 下位ビットから順に読み出すビット単位の読み取り器
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    bit_buf: u32,
+    bit_count: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0, bit_buf: 0, bit_count: 0 }
+    }
+    // nビット(最大16ビット)を読み出す
+    fn bits(&mut self, n: u32) -> Result<u32, Error> {
+        while self.bit_count < n {
+            let byte = *self.data.get(self.pos).ok_or_else(truncated)?;
+            self.bit_buf |= (byte as u32) << self.bit_count;
+            self.bit_count += 8;
+            self.pos += 1;
+        }
+        let value = self.bit_buf & ((1 << n) - 1);
+        self.bit_buf >>= n;
+        self.bit_count -= n;
+        return Ok(value);
+    }
+    // 読みかけのバイトの残りのビットを捨てて、バイト境界に揃える
+    fn align_to_byte(&mut self) {
+        self.bit_buf = 0;
+        self.bit_count = 0;
+    }
+    // バイト境界から1バイトを読み出す
+    fn byte(&mut self) -> Result<u8, Error> {
+        let byte = *self.data.get(self.pos).ok_or_else(truncated)?;
+        self.pos += 1;
+        return Ok(byte);
+    }
+}
+
+// 符号長の一覧から作る正規ハフマン符号
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0u16; 16];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+        // 符号長ごとの最初の記号の位置を求め、記号を符号長、記号の順に並べる
+        let mut offsets = [0u16; 16];
+        for i in 1..15 {
+            offsets[i + 1] = offsets[i] + counts[i];
+        }
+        let mut symbols = alloc::vec![0u16; lengths.len()];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length != 0 {
+                symbols[offsets[length as usize] as usize] = symbol as u16;
+                offsets[length as usize] += 1;
+            }
+        }
+        Self { counts, symbols }
+    }
+    // 1ビットずつ読み進めて、一致する符号の記号を返す
+    fn decode(&self, reader: &mut BitReader) -> Result<u16, Error> {
+        let mut code: i32 = 0;
+        let mut first: i32 = 0;
+        let mut index: i32 = 0;
+        for length in 1..16 {
+            code |= reader.bits(1)? as i32;
+            let count = self.counts[length] as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        return Err(Error::Network("invalid huffman code".to_string()));
+    }
+}
+
+// 固定ハフマン符号(リテラル/長さの符号と距離の符号)を返す
+fn fixed_huffman() -> (Huffman, Huffman) {
+    let mut lengths = [0u8; 288];
+    for (symbol, length) in lengths.iter_mut().enumerate() {
+        *length = match symbol {
+            0..=143 => 8,
+            144..=255 => 9,
+            256..=279 => 7,
+            _ => 8,
+        };
+    }
+    return (Huffman::new(&lengths), Huffman::new(&[5u8; 30]));
+}
+
+// 動的ハフマン符号のブロックの先頭から、リテラル/長さの符号と距離の符号を読み出す
+fn dynamic_huffman(reader: &mut BitReader) -> Result<(Huffman, Huffman), Error> {
+    let literal_count = reader.bits(5)? as usize + 257;
+    let distance_count = reader.bits(5)? as usize + 1;
+    let code_length_count = reader.bits(4)? as usize + 4;
+
+    let mut code_lengths = [0u8; 19];
+    for &index in CODE_LENGTH_ORDER.iter().take(code_length_count) {
+        code_lengths[index] = reader.bits(3)? as u8;
+    }
+    let code_length_huffman = Huffman::new(&code_lengths);
+
+    // リテラル/長さと距離の符号長は続けて符号化されている
+    let mut lengths: Vec<u8> = Vec::new();
+    while lengths.len() < literal_count + distance_count {
+        let symbol = code_length_huffman.decode(reader)?;
+        let (value, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            // 直前の符号長を3〜6回繰り返す
+            16 => match lengths.last() {
+                Some(&previous) => (previous, 3 + reader.bits(2)?),
+                None => return Err(Error::Network("invalid code length repeat".to_string())),
+            },
+            // 0を3〜10回、もしくは11〜138回繰り返す
+            17 => (0, 3 + reader.bits(3)?),
+            _ => (0, 11 + reader.bits(7)?),
+        };
+        for _ in 0..repeat {
+            lengths.push(value);
+        }
+    }
+    if lengths.len() > literal_count + distance_count {
+        return Err(Error::Network("too many code lengths".to_string()));
+    }
+    let (literal_lengths, distance_lengths) = lengths.split_at(literal_count);
+    return Ok((Huffman::new(literal_lengths), Huffman::new(distance_lengths)));
+}
+
+// ハフマン符号で圧縮されたブロックを、ブロックの終わり(256)まで展開する
+// 展開したデータがmax_lengthバイトを超える場合はエラーにする
+fn inflate_block(
+    reader: &mut BitReader,
+    output: &mut Vec<u8>,
+    literal: &Huffman,
+    distance: &Huffman,
+    max_length: usize,
+) -> Result<(), Error> {
+    loop {
+        let symbol = literal.decode(reader)? as usize;
+        if symbol < 256 {
+            if output.len() >= max_length {
+                return Err(too_large());
+            }
+            output.push(symbol as u8);
+            continue;
+        }
+        if symbol == 256 {
+            return Ok(());
+        }
+        // 長さと距離の組の場合、すでに展開したデータをコピーする
+        let index = symbol - 257;
+        if index >= LENGTH_BASE.len() {
+            return Err(Error::Network("invalid length code".to_string()));
+        }
+        let length = LENGTH_BASE[index] as usize + reader.bits(LENGTH_EXTRA[index] as u32)? as usize;
+        let index = distance.decode(reader)? as usize;
+        if index >= DISTANCE_BASE.len() {
+            return Err(Error::Network("invalid distance code".to_string()));
+        }
+        let distance = DISTANCE_BASE[index] as usize + reader.bits(DISTANCE_EXTRA[index] as u32)? as usize;
+        if distance > output.len() {
+            return Err(Error::Network("distance is too far back".to_string()));
+        }
+        if output.len() + length > max_length {
+            return Err(too_large());
+        }
+        let start = output.len() - distance;
+        for i in 0..length {
+            output.push(output[start + i]);
+        }
+    }
+}
+
+// deflate形式(RFC 1951)のデータをmax_lengthバイトまで展開し、展開したデータと読んだバイト数を返す
+fn inflate_with_length(data: &[u8], max_length: usize) -> Result<(Vec<u8>, usize), Error> {
+    let mut reader = BitReader::new(data);
+    let mut output = Vec::new();
+    loop {
+        let is_final = reader.bits(1)? == 1;
+        match reader.bits(2)? {
+            // 無圧縮のブロック
+            0 => {
+                reader.align_to_byte();
+                let length = reader.byte()? as u16 | (reader.byte()? as u16) << 8;
+                let inverted = reader.byte()? as u16 | (reader.byte()? as u16) << 8;
+                if length != !inverted {
+                    return Err(Error::Network("invalid stored block length".to_string()));
+                }
+                if output.len() + length as usize > max_length {
+                    return Err(too_large());
+                }
+                for _ in 0..length {
+                    output.push(reader.byte()?);
+                }
+            }
+            1 => {
+                let (literal, distance) = fixed_huffman();
+                inflate_block(&mut reader, &mut output, &literal, &distance, max_length)?;
+            }
+            2 => {
+                let (literal, distance) = dynamic_huffman(&mut reader)?;
+                inflate_block(&mut reader, &mut output, &literal, &distance, max_length)?;
+            }
+            _ => return Err(Error::Network("invalid block type".to_string())),
+        }
+        if is_final {
+            return Ok((output, reader.pos));
+        }
+    }
+}
+
+// deflate形式(RFC 1951)のデータを展開する
+pub fn inflate(data: &[u8]) -> Result<Vec<u8>, Error> {
+    return Ok(inflate_with_length(data, MAX_OUTPUT_LENGTH)?.0);
+}
+
+// zlib形式(RFC 1950)のデータを展開する
+pub fn zlib_decompress(data: &[u8]) -> Result<Vec<u8>, Error> {
+    if data.len() < 2 {
+        return Err(truncated());
+    }
+    let (cmf, flg) = (data[0], data[1]);
+    if cmf & 0x0F != 8 || !(cmf as u16 * 256 + flg as u16).is_multiple_of(31) {
+        return Err(Error::Network("invalid zlib header".to_string()));
+    }
+    if flg & 0x20 != 0 {
+        return Err(Error::Network("zlib preset dictionary is not supported".to_string()));
+    }
+    let (output, length) = inflate_with_length(&data[2..], MAX_OUTPUT_LENGTH)?;
+    // 末尾のAdler-32を検証する
+    let trailer = data.get(2 + length..2 + length + 4).ok_or_else(truncated)?;
+    let expected = u32::from_be_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+    if adler32(&output) != expected {
+        return Err(Error::Network("zlib checksum mismatch".to_string()));
+    }
+    return Ok(output);
+}
+
+// gzip形式(RFC 1952)のデータを展開する
+pub fn gunzip(data: &[u8]) -> Result<Vec<u8>, Error> {
+    if data.len() < 10 {
+        return Err(truncated());
+    }
+    if data[0] != 0x1F || data[1] != 0x8B || data[2] != 8 {
+        return Err(Error::Network("invalid gzip header".to_string()));
+    }
+    let flags = data[3];
+    let mut pos = 10;
+    // FEXTRA: 2バイトの長さに続く追加フィールド
+    if flags & 0x04 != 0 {
+        let extra = data.get(pos..pos + 2).ok_or_else(truncated)?;
+        pos += 2 + (extra[0] as usize | (extra[1] as usize) << 8);
+    }
+    // FNAME、FCOMMENT: 0で終わる文字列
+    for flag in [0x08, 0x10] {
+        if flags & flag != 0 {
+            let end = data.get(pos..).and_then(|rest| rest.iter().position(|&b| b == 0)).ok_or_else(truncated)?;
+            pos += end + 1;
+        }
+    }
+    // FHCRC: ヘッダのCRC-16
+    if flags & 0x02 != 0 {
+        pos += 2;
+    }
+    let (output, length) = inflate_with_length(data.get(pos..).ok_or_else(truncated)?, MAX_OUTPUT_LENGTH)?;
+    // 末尾のCRC-32と展開後のサイズを検証する
+    let trailer = data.get(pos + length..pos + length + 8).ok_or_else(truncated)?;
+    let crc = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+    let size = u32::from_le_bytes([trailer[4], trailer[5], trailer[6], trailer[7]]);
+    if crc32(&output) != crc || output.len() as u32 != size {
+        return Err(Error::Network("gzip checksum mismatch".to_string()));
+    }
+    return Ok(output);
+}
+
+// CRC-32を計算する
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = match crc & 1 {
+                1 => (crc >> 1) ^ 0xEDB8_8320,
+                _ => crc >> 1,
+            };
+        }
+    }
+    return !crc;
+}
+
+// Adler-32を計算する
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    return (b << 16) | a;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // "hello, world\n" をgzipで圧縮したデータ
+    const HELLO_GZIP: [u8; 33] = [
+        0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xcb, 0x48, 0xcd, 0xc9, 0xc9, 0xd7, 0x51, 0x28,
+        0xcf, 0x2f, 0xca, 0x49, 0xe1, 0x02, 0x00, 0x53, 0x74, 0x24, 0xf4, 0x0d, 0x00, 0x00, 0x00,
+    ];
+
+    // 固定ハフマン符号で圧縮したgzipを展開する場合
+    #[test]
+    fn test_gunzip() {
+        assert_eq!(gunzip(&HELLO_GZIP), Ok(b"hello, world\n".to_vec()));
+    }
+
+    // 動的ハフマン符号で圧縮したzlibを展開する場合
+    #[test]
+    fn test_zlib_dynamic_huffman() {
+        let data = [
+            0x78, 0xda, 0x4d, 0x8d, 0x41, 0x0e, 0x80, 0x30, 0x08, 0x04, 0xbf, 0xc2, 0xd7, 0x5a, 0x21, 0x31, 0xb1, 0x2d,
+            0x06, 0x68, 0xfc, 0xbe, 0xba, 0xd5, 0xea, 0x01, 0xb2, 0x19, 0x26, 0x2c, 0x6b, 0xa5, 0x6c, 0x7a, 0xb8, 0x18,
+            0xad, 0x51, 0x0b, 0x75, 0x2b, 0x3f, 0x10, 0x3b, 0xf1, 0x65, 0x58, 0xf7, 0x40, 0x08, 0xdd, 0xa4, 0xc1, 0xb9,
+            0xa7, 0x29, 0x0b, 0x2d, 0x6a, 0xf2, 0x49, 0x40, 0x9e, 0x72, 0x1a, 0x7c, 0xf8, 0xb8, 0x60, 0xa1, 0x62, 0xbe,
+            0x45, 0x78, 0xcb, 0xe6, 0x9f, 0x07, 0x9c, 0x49, 0x24, 0x39, 0x2c,
+        ];
+        let expected = "dom browser html url browser http dom rust dom token url url node core dom rust node saba core token rust rust html http dom http browser core dom browser";
+        assert_eq!(zlib_decompress(&data), Ok(expected.as_bytes().to_vec()));
+    }
+
+    // 無圧縮のブロックと、zlibヘッダのないdeflateを展開する場合
+    #[test]
+    fn test_stored_block_and_raw_deflate() {
+        let stored = [0x78, 0x01, 0x01, 0x06, 0x00, 0xf9, 0xff, 0x73, 0x74, 0x6f, 0x72, 0x65, 0x64, 0x09, 0x3c, 0x02, 0x92];
+        assert_eq!(zlib_decompress(&stored), Ok(b"stored".to_vec()));
+
+        let raw = [0x4b, 0x4c, 0x4a, 0x4e, 0xc4, 0x40, 0x00];
+        assert_eq!(inflate(&raw), Ok(b"abcabcabcabcabcabcabc".to_vec()));
+    }
+
+    // 展開後のサイズが上限を超える、繰り返しの多いデータの場合
+    #[test]
+    fn test_output_length_limit() {
+        // "a" を100000個並べたデータを、長さと距離の組の繰り返しで圧縮したdeflate
+        let mut data = alloc::vec![
+            0xed, 0xc1, 0x31, 0x01, 0x00, 0x00, 0x00, 0xc2, 0xa0, 0xac, 0xeb, 0x5f, 0xc2, 0x1a, 0x1e, 0x40, 0x01,
+        ];
+        data.extend_from_slice(&[0x00; 96]);
+        data.extend_from_slice(&[0xaf, 0x06]);
+        assert_eq!(inflate(&data).map(|output| output.len()), Ok(100000));
+
+        assert_eq!(inflate_with_length(&data, 100000).map(|(output, _)| output.len()), Ok(100000));
+        assert_eq!(inflate_with_length(&data, 99999), Err(too_large()));
+        assert_eq!(inflate_with_length(&data, 1000), Err(too_large()));
+        assert_eq!(inflate_with_length(&data, 0), Err(too_large()));
+
+        // 無圧縮のブロックも上限を超える場合はエラーにする
+        let stored = [0x01, 0x06, 0x00, 0xf9, 0xff, 0x73, 0x74, 0x6f, 0x72, 0x65, 0x64];
+        assert_eq!(inflate_with_length(&stored, 5), Err(too_large()));
+    }
+
+    // 途中で終わっているgzipはエラーになる場合
+    #[test]
+    fn test_truncated_gzip() {
+        assert_eq!(gunzip(&HELLO_GZIP[..20]), Err(truncated()));
+        assert_eq!(gunzip(&HELLO_GZIP[..30]), Err(truncated()));
+        assert!(gunzip(b"not gzip data").is_err());
+    }
+}
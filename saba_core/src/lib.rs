@@ -4,5 +4,6 @@ extern crate alloc;
 
 pub mod error;
 pub mod http;
+pub mod inflate;
 pub mod renderer;
 pub mod url;
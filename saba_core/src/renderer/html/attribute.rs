@@ -1,4 +1,73 @@
-use alloc::string::String;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use crate::url::Url;
+
+// 存在するだけで真を表す真偽値属性
+const BOOLEAN_ATTRIBUTES: &[&str] = &[
+    "async", "autofocus", "autoplay", "checked", "controls", "default", "defer", "disabled",
+    "formnovalidate", "hidden", "ismap", "loop", "multiple", "muted", "nomodule", "novalidate",
+    "open", "readonly", "required", "reversed", "selected",
+];
+
+// 属性名が真偽値属性かどうかを判定する(大文字小文字は区別しない)
+pub fn is_boolean_attribute(name: &str) -> bool {
+    return BOOLEAN_ATTRIBUTES.iter().any(|a| a.eq_ignore_ascii_case(name));
+}
+
+// 属性名がonclickやonloadのようなイベントハンドラ属性(on で始まる)かどうかを判定する(大文字小文字は区別しない)
+pub fn is_event_handler_attribute(name: &str) -> bool {
+    return name.len() > 2 && name.get(..2).is_some_and(|prefix| prefix.eq_ignore_ascii_case("on"));
+}
+
+// srcset属性の値を候補ごとに分割し、baseを基準に解決したURLと記述子(2x, 480wなど)の組を返す
+pub fn parse_srcset(value: &str, base: &Url) -> Vec<(Url, Option<String>)> {
+    let mut candidates = Vec::new();
+    for candidate in value.split(',') {
+        let mut parts = candidate.split_whitespace();
+        let url = match parts.next() {
+            Some(u) => u,
+            None => continue,
+        };
+        let descriptor: Vec<&str> = parts.collect();
+        let descriptor = match descriptor.is_empty() {
+            true => None,
+            false => Some(descriptor.join(" ")),
+        };
+        // 解決できないURLの候補は読み捨てる
+        if let Ok(resolved) = base.join(url) {
+            candidates.push((resolved, descriptor));
+        }
+    }
+    return candidates;
+}
+
+// integrity属性の値を空白で分割し、ハッシュアルゴリズムとbase64のハッシュ値の組を返す
+// (sha256-abc?opt のような ? 以降のオプションは無視し、- を含まない値は読み捨てる)
+pub fn parse_integrity(value: &str) -> Vec<(String, String)> {
+    let mut hashes = Vec::new();
+    for metadata in value.split_whitespace() {
+        let metadata = metadata.split('?').next().unwrap_or("");
+        if let Some((algorithm, hash)) = metadata.split_once('-') {
+            if !algorithm.is_empty() && !hash.is_empty() {
+                hashes.push((algorithm.to_ascii_lowercase(), hash.to_string()));
+            }
+        }
+    }
+    return hashes;
+}
+
+// script要素かlink要素のintegrity属性からハッシュアルゴリズムとハッシュ値の組を返す
+pub fn integrity_hashes(tag: &str, attributes: &[Attribute]) -> Vec<(String, String)> {
+    if !tag.eq_ignore_ascii_case("script") && !tag.eq_ignore_ascii_case("link") {
+        return Vec::new();
+    }
+    for attribute in attributes {
+        if attribute.name.eq_ignore_ascii_case("integrity") {
+            return parse_integrity(&attribute.value);
+        }
+    }
+    return Vec::new();
+}
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Attribute{
@@ -30,4 +99,95 @@ impl Attribute {
     pub fn value(&self) -> String {
         return self.value.clone();
     }
+
+    // 属性を真偽値として評価する
+    // 真偽値属性は値に関わらず存在するだけでtrue、それ以外の属性は値が空でなければtrueとする
+    pub fn as_bool(&self) -> bool {
+        if is_boolean_attribute(&self.name) {
+            return true;
+        }
+        return !self.value.is_empty();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn attribute(name: &str, value: &str) -> Attribute {
+        let mut attr = Attribute::new();
+        for c in name.chars() {
+            attr.add_char(c, true);
+        }
+        for c in value.chars() {
+            attr.add_char(c, false);
+        }
+        return attr;
+    }
+
+    // 真偽値属性の判定
+    #[test]
+    fn test_is_boolean_attribute() {
+        assert!(is_boolean_attribute("checked"));
+        assert!(is_boolean_attribute("DISABLED"));
+        assert!(!is_boolean_attribute("value"));
+    }
+
+    // 真偽値属性は値が空でも、"false"でもtrueになる
+    #[test]
+    fn test_boolean_attribute_as_bool() {
+        assert!(attribute("checked", "").as_bool());
+        assert!(attribute("checked", "false").as_bool());
+    }
+
+    // 真偽値属性以外は値が空でなければtrueになる
+    #[test]
+    fn test_non_boolean_attribute_as_bool() {
+        assert!(attribute("value", "x").as_bool());
+        assert!(!attribute("value", "").as_bool());
+    }
+
+    // 記述子を持つ候補が複数ある場合
+    #[test]
+    fn test_parse_srcset() {
+        let base = Url::new("http://example.com/img/index.html".to_string()).parse().expect("failed to parse url");
+        let candidates = parse_srcset("a.png 1x, /b.png 2x", &base);
+        assert_eq!(candidates.len(), 2);
+        assert_eq!(candidates[0].0.path(), "img/a.png".to_string());
+        assert_eq!(candidates[0].1, Some("1x".to_string()));
+        assert_eq!(candidates[1].0.path(), "b.png".to_string());
+        assert_eq!(candidates[1].1, Some("2x".to_string()));
+    }
+
+    // 記述子が省略された候補や絶対URLの候補がある場合
+    #[test]
+    fn test_parse_srcset_without_descriptor() {
+        let base = Url::new("http://example.com/".to_string()).parse().expect("failed to parse url");
+        let candidates = parse_srcset("http://cdn.example.com/a.png, b.png 480w,", &base);
+        assert_eq!(candidates.len(), 2);
+        assert_eq!(candidates[0].0.host(), "cdn.example.com".to_string());
+        assert_eq!(candidates[0].1, None);
+        assert_eq!(candidates[1].0.path(), "b.png".to_string());
+        assert_eq!(candidates[1].1, Some("480w".to_string()));
+    }
+
+    // integrity属性が複数のハッシュを持つ場合
+    #[test]
+    fn test_integrity_hashes() {
+        let attributes = [attribute("src", "a.js"), attribute("integrity", "sha256-abc sha384-def")];
+        let expected = vec![
+            ("sha256".to_string(), "abc".to_string()),
+            ("sha384".to_string(), "def".to_string()),
+        ];
+        assert_eq!(integrity_hashes("script", &attributes), expected);
+        assert!(integrity_hashes("img", &attributes).is_empty());
+    }
+
+    // オプションや不正な値を含む場合
+    #[test]
+    fn test_parse_integrity_options_and_invalid() {
+        let expected = vec![("sha512".to_string(), "xyz".to_string())];
+        assert_eq!(parse_integrity("  SHA512-xyz?ct=text/javascript invalid -abc "), expected);
+    }
 }
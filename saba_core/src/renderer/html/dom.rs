@@ -0,0 +1,1708 @@
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use crate::renderer::html::attribute::{is_event_handler_attribute, parse_srcset, Attribute};
+use crate::renderer::html::{escape_attribute, escape_text, is_void_element};
+use crate::renderer::html::token::{HtmlToken, HtmlTokenizer};
+use crate::url::Url;
+
+// 開始タグが来たときに、開いている同じ種類の要素を暗黙に閉じる要素
+// (例: <li> の中で次の <li> が始まったら前の <li> を閉じる)
+const IMPLICITLY_CLOSED: &[(&str, &[&str])] = &[
+    ("li", &["li"]),
+    ("dt", &["dt", "dd"]),
+    ("dd", &["dt", "dd"]),
+    ("p", &["p"]),
+    ("option", &["option"]),
+];
+
+// テキストとして描画する際に、前後で改行するブロック要素
+const BLOCK_ELEMENTS: &[&str] = &[
+    "address", "article", "aside", "blockquote", "body", "dd", "div", "dl", "dt", "fieldset", "figure", "footer",
+    "form", "h1", "h2", "h3", "h4", "h5", "h6", "header", "hr", "html", "li", "main", "nav", "ol", "p", "pre",
+    "section", "table", "tr", "ul",
+];
+
+// <body>より前にある場合に<head>に入れる要素
+const HEAD_ELEMENTS: &[&str] = &["base", "link", "meta", "noscript", "script", "style", "template", "title"];
+
+// テキストとして描画しない要素
+const NON_RENDERED_ELEMENTS: &[&str] = &["head", "script", "style", "template"];
+
+// 整形して出力する際に、要素と同じ行にまとめるテキストの最大の文字数
+const PRETTY_PRINT_INLINE_TEXT_LEN: usize = 40;
+
+// ノードの種類
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeKind {
+    // ツリーの根(build_domが作る仮の根)
+    Document,
+    // 要素(タグ名と属性)
+    Element {
+        tag: String,
+        attributes: Vec<Attribute>,
+    },
+    // 連続する文字をまとめたテキスト
+    Text(String),
+    // コメント
+    Comment(String),
+}
+
+// DOMツリーのノード(子ノードを所有する)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Node {
+    kind: NodeKind,
+    children: Vec<Node>,
+}
+
+impl Node {
+    pub fn new(kind: NodeKind) -> Self {
+        Self {
+            kind,
+            children: Vec::new(),
+        }
+    }
+
+    // ゲッター
+    pub fn kind(&self) -> &NodeKind {
+        return &self.kind;
+    }
+    pub fn children(&self) -> &[Node] {
+        return &self.children;
+    }
+
+    // 要素の場合、タグ名を返す
+    pub fn tag_name(&self) -> Option<&str> {
+        match &self.kind {
+            NodeKind::Element { tag, .. } => return Some(tag),
+            _ => return None,
+        }
+    }
+    // 要素の場合、指定した名前(大文字小文字は区別しない)の属性の値を返す
+    pub fn get_attribute(&self, name: &str) -> Option<String> {
+        match &self.kind {
+            NodeKind::Element { attributes, .. } => {
+                return attributes.iter().find(|a| a.name().eq_ignore_ascii_case(name)).map(|a| a.value());
+            }
+            _ => return None,
+        }
+    }
+
+    // 子孫のテキストノードを文書の順に連結した文字列を返す
+    // hidden属性を持つ子孫の要素は、その中のテキストごと含めない
+    pub fn text_content(&self) -> String {
+        if let NodeKind::Text(text) = &self.kind {
+            return text.clone();
+        }
+        let mut text = String::new();
+        for child in &self.children {
+            if matches!(child.kind, NodeKind::Comment(_)) || child.get_attribute("hidden").is_some() {
+                continue;
+            }
+            text.push_str(&child.text_content());
+        }
+        return text;
+    }
+
+    // 子ノードを末尾に追加する
+    // 直前の子ノードがテキストの場合、テキストは1つのノードにまとめる
+    pub fn append_child(&mut self, child: Node) {
+        if let NodeKind::Text(text) = &child.kind {
+            if let Some(Node { kind: NodeKind::Text(last), .. }) = self.children.last_mut() {
+                last.push_str(text);
+                return;
+            }
+        }
+        self.children.push(child);
+    }
+}
+
+// 空白文字(\t \n \x0C \r と空白)のトークンかどうかを判定する
+fn is_whitespace_token(token: &HtmlToken) -> bool {
+    return matches!(token, HtmlToken::Char(c) if c.is_ascii_whitespace());
+}
+
+// 要素を作る(補った要素など属性を持たない要素を作る場合はattributesを空にする)
+fn new_element(tag: &str, attributes: Vec<Attribute>) -> Node {
+    return Node::new(NodeKind::Element { tag: tag.to_string(), attributes });
+}
+
+// 開いている<html>の子の<head>か<body>のタグ名を返す
+fn open_section(stack: &[Node]) -> Option<&str> {
+    return stack.get(2).and_then(|n| n.tag_name());
+}
+
+// 省略された<html>、<head>、<body>を補い、トークンを通常通り処理するかを返す
+// <html>より前の空白は読み捨て、コメントは文書の子にする
+// <head>に入る要素は<body>より前なら<head>に入れ、それ以外の要素や文字の前に<body>を補う
+// <html>、<head>、<body>の開始タグと終了タグは、それぞれを1つに保つために読み捨てる
+// (終了タグの後の要素も<body>の中に入る)
+// 要素を補って挿入モードが変わるたびに、traceが有効であれば記録する
+fn insert_implied_elements(stack: &mut Vec<Node>, token: &HtmlToken, trace: &mut Option<Vec<InsertionMode>>) -> bool {
+    if let HtmlToken::StartTag { tag, attributes, .. } = token {
+        if tag == "html" {
+            if stack.len() == 1 {
+                record_insertion_mode(trace, InsertionMode::BeforeHtml);
+                stack.push(new_element(tag, attributes.clone()));
+                record_insertion_mode(trace, current_insertion_mode(stack));
+            }
+            return false;
+        }
+    }
+    if let HtmlToken::EndTag { tag } = token {
+        if tag == "html" || tag == "body" || (tag == "head" && open_section(stack) != Some("head")) {
+            return false;
+        }
+    }
+
+    // before html: <html>がまだない
+    if stack.len() == 1 {
+        match token {
+            _ if is_whitespace_token(token) => return false,
+            HtmlToken::Comment(_) => return true,
+            HtmlToken::Doctype { .. } => {
+                record_insertion_mode(trace, InsertionMode::BeforeHtml);
+                return true;
+            }
+            _ => {
+                record_insertion_mode(trace, InsertionMode::BeforeHtml);
+                stack.push(new_element("html", Vec::new()));
+                record_insertion_mode(trace, current_insertion_mode(stack));
+            }
+        }
+    }
+
+    match open_section(stack) {
+        Some("body") => {
+            return !matches!(token, HtmlToken::StartTag { tag, .. } if tag == "head" || tag == "body");
+        }
+        Some("head") => {
+            // <title>などの中
+            if stack.len() > 3 {
+                return true;
+            }
+            match token {
+                HtmlToken::StartTag { tag, .. } if HEAD_ELEMENTS.contains(&tag.as_str()) => return true,
+                HtmlToken::Comment(_) | HtmlToken::Doctype { .. } => return true,
+                _ if is_whitespace_token(token) => return true,
+                HtmlToken::EndTag { tag } if tag == "head" => {
+                    close_current(stack);
+                    record_insertion_mode(trace, current_insertion_mode(stack));
+                    return false;
+                }
+                // <head>に入らない要素や文字の前で<head>を閉じる
+                _ => {
+                    close_current(stack);
+                    record_insertion_mode(trace, current_insertion_mode(stack));
+                }
+            }
+        }
+        _ => {}
+    }
+
+    // <head>の前か後で、<body>の前
+    let has_head = stack[1].children().iter().any(|n| n.tag_name() == Some("head"));
+    match token {
+        _ if is_whitespace_token(token) => return false,
+        HtmlToken::Comment(_) | HtmlToken::Doctype { .. } => return true,
+        HtmlToken::StartTag { tag, attributes, .. } if tag == "head" => {
+            if !has_head {
+                stack.push(new_element(tag, attributes.clone()));
+                record_insertion_mode(trace, current_insertion_mode(stack));
+            }
+            return false;
+        }
+        HtmlToken::StartTag { tag, .. } if !has_head && HEAD_ELEMENTS.contains(&tag.as_str()) => {
+            stack.push(new_element("head", Vec::new()));
+            record_insertion_mode(trace, current_insertion_mode(stack));
+            return true;
+        }
+        HtmlToken::EndTag { .. } => return false,
+        _ => {}
+    }
+    if !has_head {
+        stack.push(new_element("head", Vec::new()));
+        record_insertion_mode(trace, current_insertion_mode(stack));
+        close_current(stack);
+        record_insertion_mode(trace, current_insertion_mode(stack));
+    }
+    if let HtmlToken::StartTag { tag, attributes, .. } = token {
+        if tag == "body" {
+            stack.push(new_element(tag, attributes.clone()));
+            record_insertion_mode(trace, current_insertion_mode(stack));
+            return false;
+        }
+    }
+    stack.push(new_element("body", Vec::new()));
+    record_insertion_mode(trace, current_insertion_mode(stack));
+    return true;
+}
+
+// テキストの連続する空白を1つにまとめ、前後の空白を取り除く
+fn collapse_whitespace(text: &str) -> String {
+    return text.split_ascii_whitespace().collect::<Vec<&str>>().join(" ");
+}
+
+// 空白のみのテキストノードを除いた子ノードを返す
+fn significant_children(node: &Node) -> Vec<&Node> {
+    return node
+        .children()
+        .iter()
+        .filter(|child| !matches!(child.kind(), NodeKind::Text(text) if text.trim_ascii().is_empty()))
+        .collect();
+}
+
+// 空白の違いを無視して2つのツリーが等しいかを判定する
+// 空白のみのテキストノードはないものとして扱い、テキストは連続する空白を1つにまとめ、前後の空白を取り除いて比較する
+pub fn tree_eq_ignoring_whitespace(a: &Node, b: &Node) -> bool {
+    let kind_eq = match (a.kind(), b.kind()) {
+        (NodeKind::Text(a), NodeKind::Text(b)) => collapse_whitespace(a) == collapse_whitespace(b),
+        (a, b) => a == b,
+    };
+    if !kind_eq {
+        return false;
+    }
+    let (a_children, b_children) = (significant_children(a), significant_children(b));
+    return a_children.len() == b_children.len()
+        && a_children.iter().zip(b_children.iter()).all(|(a, b)| tree_eq_ignoring_whitespace(a, b));
+}
+
+// 開いている要素のスタックの一番上を閉じ、親の子ノードに追加する
+fn close_current(stack: &mut Vec<Node>) {
+    // 根は閉じない
+    if stack.len() < 2 {
+        return;
+    }
+    let node = stack.pop().expect("stack should have an open element");
+    stack.last_mut().expect("stack should have a parent").append_child(node);
+}
+
+// 挿入モード(パース中にどの要素の中にいるか)
+// build_domはスタックの開いている要素から挿入モードを求め、遷移の記録のためだけに使う
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InsertionMode {
+    Initial,
+    BeforeHtml,
+    BeforeHead,
+    InHead,
+    AfterHead,
+    InBody,
+    Text,
+    InTable,
+    InTableBody,
+    InRow,
+    InCell,
+    AfterBody,
+}
+
+// 開いている要素のスタックから現在の挿入モードを求める
+fn current_insertion_mode(stack: &[Node]) -> InsertionMode {
+    // まだ<html>がない
+    if stack.len() < 2 {
+        return InsertionMode::BeforeHtml;
+    }
+    // scriptなど中身を文字として扱う要素の中
+    if let Some("script" | "style" | "title" | "textarea") = stack.last().and_then(|n| n.tag_name()) {
+        return InsertionMode::Text;
+    }
+    match open_section(stack) {
+        Some("head") => return InsertionMode::InHead,
+        Some(_) => {}
+        None if stack[1].children().iter().any(|n| n.tag_name() == Some("head")) => return InsertionMode::AfterHead,
+        None => return InsertionMode::BeforeHead,
+    }
+    for node in stack.iter().rev() {
+        match node.tag_name() {
+            Some("td" | "th") => return InsertionMode::InCell,
+            Some("tr") => return InsertionMode::InRow,
+            Some("tbody" | "thead" | "tfoot") => return InsertionMode::InTableBody,
+            Some("table") => return InsertionMode::InTable,
+            _ => {}
+        }
+    }
+    return InsertionMode::InBody;
+}
+
+// traceが有効な場合、挿入モードが直前の記録と異なれば記録する
+fn record_insertion_mode(trace: &mut Option<Vec<InsertionMode>>, mode: InsertionMode) {
+    if let Some(trace) = trace.as_mut() {
+        if trace.last() != Some(&mode) {
+            trace.push(mode);
+        }
+    }
+}
+
+// トークン列からDOMツリーを作るパーサ
+pub struct HtmlParser<'a> {
+    tokenizer: HtmlTokenizer<'a>,
+    trace: Option<Vec<InsertionMode>>,
+}
+
+impl<'a> HtmlParser<'a> {
+    pub fn new(tokenizer: HtmlTokenizer<'a>) -> Self {
+        Self { tokenizer, trace: None }
+    }
+
+    // 挿入モードの遷移を記録する(デバッグ用)
+    pub fn set_trace_insertion_modes(&mut self, enabled: bool) {
+        self.trace = match enabled {
+            true => Some(Vec::new()),
+            false => None,
+        };
+    }
+    // 記録した挿入モードを遷移した順に返す(記録していない場合は空)
+    pub fn insertion_mode_trace(&self) -> &[InsertionMode] {
+        return self.trace.as_deref().unwrap_or(&[]);
+    }
+    // 挿入モードが変わった場合に記録する
+    fn enter_insertion_mode(&mut self, mode: InsertionMode) {
+        record_insertion_mode(&mut self.trace, mode);
+    }
+
+    // 開いている要素のスタックを使い、終了タグが一番上の要素と一致しない場合は一致する祖先まで閉じる
+    // 一致する祖先がない終了タグやDOCTYPE宣言は読み捨てる
+    // 文書の要素は必ず1つの<html>の子孫になる(省略されている場合は補う)
+    pub fn construct_tree(&mut self) -> Node {
+        let mut stack = Vec::new();
+        stack.push(Node::new(NodeKind::Document));
+        self.enter_insertion_mode(InsertionMode::Initial);
+        // </body>か</html>の後で、空白とコメントしか現れていないか
+        let mut after_body = false;
+        while let Some(token) = self.tokenizer.next() {
+            // </body>と</html>は読み捨てるが、<body>の中であれば挿入モードはafter bodyになる
+            // after bodyの空白とコメントは挿入モードを変えず、それ以外のトークンでin bodyに戻る
+            if matches!(&token, HtmlToken::EndTag { tag } if tag == "body" || tag == "html")
+                && open_section(&stack) == Some("body")
+            {
+                after_body = true;
+                self.enter_insertion_mode(InsertionMode::AfterBody);
+            } else if !is_whitespace_token(&token) && !matches!(token, HtmlToken::Comment(_)) {
+                after_body = false;
+            }
+            if !insert_implied_elements(&mut stack, &token, &mut self.trace) {
+                continue;
+            }
+            match token {
+                HtmlToken::StartTag { tag, self_closing, attributes } => {
+                    if let Some((_, closes)) = IMPLICITLY_CLOSED.iter().find(|(t, _)| *t == tag) {
+                        if stack.last().and_then(|n| n.tag_name()).is_some_and(|current| closes.contains(&current)) {
+                            close_current(&mut stack);
+                        }
+                    }
+                    let element = Node::new(NodeKind::Element { tag: tag.clone(), attributes });
+                    // 自己終了タグや空要素は子ノードを持たないため、すぐに親に追加する
+                    if self_closing || is_void_element(&tag) {
+                        stack.last_mut().expect("stack should have the root").append_child(element);
+                    } else {
+                        stack.push(element);
+                    }
+                }
+                HtmlToken::EndTag { tag } => {
+                    if let Some(index) = stack.iter().rposition(|n| n.tag_name() == Some(tag.as_str())) {
+                        while stack.len() > index {
+                            close_current(&mut stack);
+                        }
+                    }
+                }
+                HtmlToken::Char(c) => {
+                    stack.last_mut().expect("stack should have the root").append_child(Node::new(NodeKind::Text(c.to_string())));
+                }
+                HtmlToken::Comment(comment) => {
+                    stack.last_mut().expect("stack should have the root").append_child(Node::new(NodeKind::Comment(comment)));
+                }
+                // DOCTYPE宣言では挿入モードは変わらない
+                HtmlToken::Doctype { .. } => continue,
+                HtmlToken::Eof => break,
+            }
+            if !after_body {
+                self.enter_insertion_mode(current_insertion_mode(&stack));
+            }
+        }
+        // 空の文書や<body>のない文書でも<html>、<head>、<body>を持つようにする
+        insert_implied_elements(&mut stack, &HtmlToken::Eof, &mut self.trace);
+        // 閉じられていない要素を全て閉じる(入力の終わりでは挿入モードは変わらない)
+        while stack.len() > 1 {
+            close_current(&mut stack);
+        }
+        return stack.pop().expect("stack should have the root");
+    }
+}
+
+// トークン列からDOMツリーを作る
+pub fn build_dom(tokenizer: HtmlTokenizer<'_>) -> Node {
+    return HtmlParser::new(tokenizer).construct_tree();
+}
+
+// 子の要素のうち、指定したタグ名の最初の要素を返す
+fn child_element<'a>(node: &'a Node, tag: &str) -> Option<&'a Node> {
+    return node.children().iter().find(|n| n.tag_name() == Some(tag));
+}
+
+// 文書(もしくは<html>)の<head>と<body>を返す
+// build_domは省略された<head>と<body>を補うため、build_domで作った文書では両方が返る
+pub fn split_head_body(root: &Node) -> (Option<&Node>, Option<&Node>) {
+    let html = match root.tag_name() {
+        Some("html") => Some(root),
+        _ => child_element(root, "html"),
+    };
+    return (html.and_then(|html| child_element(html, "head")), html.and_then(|html| child_element(html, "body")));
+}
+
+fn collect_elements<'a, F: Fn(&Node) -> bool>(node: &'a Node, pred: &F, found: &mut Vec<&'a Node>) {
+    if matches!(node.kind(), NodeKind::Element { .. }) && pred(node) {
+        found.push(node);
+    }
+    for child in node.children() {
+        collect_elements(child, pred, found);
+    }
+}
+
+// 条件を満たす要素を文書の順に集める(rootが要素の場合はroot自身も含む)
+pub fn find_elements<F: Fn(&Node) -> bool>(root: &Node, pred: F) -> Vec<&Node> {
+    let mut found = Vec::new();
+    collect_elements(root, &pred, &mut found);
+    return found;
+}
+
+// 子ノードのうち要素のみを返す(テキストとコメントは読み飛ばす)
+pub fn element_children(node: &Node) -> Vec<&Node> {
+    return node.children().iter().filter(|child| matches!(child.kind(), NodeKind::Element { .. })).collect();
+}
+
+// 指定したタグ名の要素を文書の順に集める
+fn elements_by_tag<'a>(node: &'a Node, tag: &str, found: &mut Vec<&'a Node>) {
+    found.extend(find_elements(node, |element| element.tag_name() == Some(tag)));
+}
+
+// <link rel="alternate" type="application/rss+xml"> とAtomの<link>から、フィードのURLをbaseを基準に解決して返す
+// relは空白区切りのいずれか、typeは大文字小文字を区別せずに比較する
+pub fn feed_links(root: &Node, base: &Url) -> Vec<Url> {
+    let mut links = Vec::new();
+    elements_by_tag(root, "link", &mut links);
+
+    let mut feeds = Vec::new();
+    for link in links {
+        let is_alternate = link.get_attribute("rel").is_some_and(|rel| {
+            rel.split_ascii_whitespace().any(|r| r.eq_ignore_ascii_case("alternate"))
+        });
+        let is_feed = link.get_attribute("type").is_some_and(|t| {
+            let t = t.trim();
+            t.eq_ignore_ascii_case("application/rss+xml") || t.eq_ignore_ascii_case("application/atom+xml")
+        });
+        if !is_alternate || !is_feed {
+            continue;
+        }
+        if let Some(Ok(url)) = link.get_attribute("href").map(|href| base.join(&href)) {
+            feeds.push(url);
+        }
+    }
+    return feeds;
+}
+
+// form要素のaccept-charset属性(空白区切りの文字コードの一覧)から最初の文字コードを返す
+pub fn form_accept_charset(form: &Node) -> Option<String> {
+    if form.tag_name() != Some("form") {
+        return None;
+    }
+    let accept_charset = form.get_attribute("accept-charset")?;
+    // 空白区切りが正しいが、, で区切られていることもある
+    return accept_charset
+        .split(|c: char| c.is_ascii_whitespace() || c == ',')
+        .find(|charset| !charset.is_empty())
+        .map(|charset| charset.to_string());
+}
+
+// 最初の<form>の送信先のURL(document_urlを基準に解決する)と大文字にしたメソッドを返す
+// actionがない場合や空の場合は文書のURLに送信し、methodがない場合や不正な値の場合はGETとする
+pub fn default_form_action(root: &Node, document_url: &Url) -> Option<(Url, String)> {
+    let mut forms = Vec::new();
+    elements_by_tag(root, "form", &mut forms);
+    let form = forms.first()?;
+    let action = match form.get_attribute("action").map(|action| action.trim().to_string()) {
+        Some(action) if !action.is_empty() => document_url.join(&action).ok()?,
+        _ => document_url.clone(),
+    };
+    let method = match form.get_attribute("method").map(|method| method.trim().to_ascii_uppercase()) {
+        Some(method) if method == "POST" || method == "DIALOG" => method,
+        _ => "GET".to_string(),
+    };
+    return Some((action, method));
+}
+
+// ファイルを送信する<form>(enctypeがmultipart/form-dataか、<input type="file">を含む<form>)があるかを判定する
+// enctypeとtypeの値は大文字小文字を区別せずに比較する
+pub fn has_file_upload(root: &Node) -> bool {
+    let mut forms = Vec::new();
+    elements_by_tag(root, "form", &mut forms);
+    return forms.iter().any(|form| {
+        let is_multipart = form
+            .get_attribute("enctype")
+            .is_some_and(|enctype| enctype.trim().eq_ignore_ascii_case("multipart/form-data"));
+        let file_inputs = find_elements(form, |element| {
+            element.tag_name() == Some("input")
+                && element.get_attribute("type").is_some_and(|t| t.trim().eq_ignore_ascii_case("file"))
+        });
+        is_multipart || !file_inputs.is_empty()
+    });
+}
+
+// <meta name="viewport" content="width=device-width, initial-scale=1"> のcontentをキー(小文字)と値の組の一覧として返す
+// 区切りは , だが ; で区切られていることもあり、値のないキーは読み捨てる
+pub fn viewport_config(root: &Node) -> Option<Vec<(String, String)>> {
+    let mut metas = Vec::new();
+    elements_by_tag(root, "meta", &mut metas);
+    let meta = metas.into_iter().find(|meta| {
+        meta.get_attribute("name").is_some_and(|name| name.trim().eq_ignore_ascii_case("viewport"))
+    })?;
+    let content = meta.get_attribute("content")?;
+
+    let mut config = Vec::new();
+    for item in content.split([',', ';']) {
+        if let Some((key, value)) = item.split_once('=') {
+            let key = key.trim();
+            if !key.is_empty() {
+                config.push((key.to_ascii_lowercase(), value.trim().to_string()));
+            }
+        }
+    }
+    return Some(config);
+}
+
+// <meta name="robots" content="noindex, nofollow"> のcontentを , で区切り、小文字にした指示の一覧を返す
+// robotsのmeta要素が複数ある場合は全ての指示を文書の順にまとめ、重複する指示は1つにする
+pub fn robots_directives(root: &Node) -> Vec<String> {
+    let mut metas = Vec::new();
+    elements_by_tag(root, "meta", &mut metas);
+
+    let mut directives: Vec<String> = Vec::new();
+    for meta in metas {
+        if !meta.get_attribute("name").is_some_and(|name| name.trim().eq_ignore_ascii_case("robots")) {
+            continue;
+        }
+        for directive in meta.get_attribute("content").unwrap_or_default().split(',') {
+            let directive = directive.trim().to_ascii_lowercase();
+            if !directive.is_empty() && !directives.contains(&directive) {
+                directives.push(directive);
+            }
+        }
+    }
+    return directives;
+}
+
+// 描画中の行が空でなければ、末尾の空白を取り除いて行の一覧に追加する
+fn flush_line(lines: &mut Vec<String>, line: &mut String) {
+    let trimmed = line.trim_end();
+    if !trimmed.is_empty() {
+        lines.push(trimmed.to_string());
+    }
+    line.clear();
+}
+
+fn render_text_node(node: &Node, lines: &mut Vec<String>, line: &mut String) {
+    match node.kind() {
+        NodeKind::Text(text) => {
+            // 連続する空白は1つの空白にまとめ、行頭の空白は出力しない
+            for c in text.chars() {
+                if c.is_ascii_whitespace() {
+                    if !line.is_empty() && !line.ends_with(' ') {
+                        line.push(' ');
+                    }
+                } else {
+                    line.push(c);
+                }
+            }
+        }
+        NodeKind::Comment(_) => {}
+        NodeKind::Document => {
+            for child in node.children() {
+                render_text_node(child, lines, line);
+            }
+        }
+        NodeKind::Element { tag, .. } => {
+            // scriptなどの描画しない要素とhidden属性を持つ要素は、子孫ごと読み飛ばす
+            if NON_RENDERED_ELEMENTS.contains(&tag.as_str()) || node.get_attribute("hidden").is_some() {
+                return;
+            }
+            // <br> は改行し、<wbr> は改行してもよい位置を示すだけなので何も出力しない
+            match tag.as_str() {
+                "br" => return flush_line(lines, line),
+                "wbr" => return,
+                _ => {}
+            }
+            let is_block = BLOCK_ELEMENTS.contains(&tag.as_str());
+            if is_block {
+                flush_line(lines, line);
+            }
+            if tag == "li" {
+                line.push_str("- ");
+            }
+            for child in node.children() {
+                render_text_node(child, lines, line);
+            }
+            if is_block {
+                flush_line(lines, line);
+            }
+        }
+    }
+}
+
+// テキストブラウザ用に、DOMツリーを文字列として描画する
+// ブロック要素と<br>は改行で区切り、インライン要素は同じ行に続け、リストの項目の先頭には "- " を付ける
+// script、styleなどの要素と、hidden属性を持つ要素は描画しない
+pub fn render_text(root: &Node) -> String {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    render_text_node(root, &mut lines, &mut line);
+    flush_line(&mut lines, &mut line);
+    return lines.join("\n");
+}
+
+// 開始タグを文字列にする(値が空の属性は名前のみを書き出す)
+fn start_tag_string(tag: &str, attributes: &[Attribute]) -> String {
+    let mut s = format!("<{}", tag);
+    for attribute in attributes {
+        if attribute.value().is_empty() {
+            s.push_str(&format!(" {}", attribute.name()));
+        } else {
+            s.push_str(&format!(" {}=\"{}\"", attribute.name(), escape_attribute(&attribute.value())));
+        }
+    }
+    s.push('>');
+    return s;
+}
+
+// テキストの連続する空白を1つにまとめ、scriptとstyleの中以外はエスケープする
+fn pretty_text(text: &str, is_raw_text: bool) -> String {
+    let text = collapse_whitespace(text);
+    if is_raw_text {
+        return text;
+    }
+    return escape_text(&text);
+}
+
+fn pretty_print_node(node: &Node, indent: usize, depth: usize, is_raw_text: bool, output: &mut String) {
+    let padding = " ".repeat(indent * depth);
+    match node.kind() {
+        NodeKind::Document => {
+            for child in node.children() {
+                pretty_print_node(child, indent, depth, false, output);
+            }
+        }
+        NodeKind::Text(text) => {
+            let text = pretty_text(text, is_raw_text);
+            if !text.is_empty() {
+                output.push_str(&format!("{}{}\n", padding, text));
+            }
+        }
+        NodeKind::Comment(comment) => output.push_str(&format!("{}<!--{}-->\n", padding, comment)),
+        NodeKind::Element { tag, attributes } => {
+            let start_tag = start_tag_string(tag, attributes);
+            if is_void_element(tag) {
+                output.push_str(&format!("{}{}\n", padding, start_tag));
+                return;
+            }
+            let is_raw_text = tag == "script" || tag == "style";
+            // 子が短いテキストのみ(もしくは子がない)場合は、開始タグと終了タグと同じ行にまとめる
+            let inline_text = match node.children() {
+                [] => Some(String::new()),
+                [child] => match child.kind() {
+                    NodeKind::Text(text) => Some(pretty_text(text, is_raw_text)),
+                    _ => None,
+                },
+                _ => None,
+            };
+            match inline_text.filter(|text| text.chars().count() <= PRETTY_PRINT_INLINE_TEXT_LEN) {
+                Some(text) => output.push_str(&format!("{}{}{}</{}>\n", padding, start_tag, text, tag)),
+                None => {
+                    output.push_str(&format!("{}{}\n", padding, start_tag));
+                    for child in node.children() {
+                        pretty_print_node(child, indent, depth + 1, is_raw_text, output);
+                    }
+                    output.push_str(&format!("{}</{}>\n", padding, tag));
+                }
+            }
+        }
+    }
+}
+
+// デバッグ用に、要素ごとに1行にし、入れ子の深さごとにindent個の空白で字下げしたHTMLを返す
+// 短いテキストのみを持つ要素は1行にまとめ、テキストの連続する空白は1つにまとめる
+pub fn pretty_print(node: &Node, indent: usize) -> String {
+    let mut output = String::new();
+    pretty_print_node(node, indent, 0, false, &mut output);
+    return output;
+}
+
+// 要素のアクセシブルな名前を返す(簡略化した優先順位)
+// aria-label属性、画像の場合はalt属性、それ以外は空白をまとめたテキストの順に探し、空の場合は次を探す
+pub fn accessible_name(element: &Node) -> Option<String> {
+    if let Some(label) = element.get_attribute("aria-label") {
+        if !label.trim().is_empty() {
+            return Some(label.trim().to_string());
+        }
+    }
+    if element.tag_name() == Some("img") {
+        return element.get_attribute("alt").map(|alt| alt.trim().to_string()).filter(|alt| !alt.is_empty());
+    }
+    let text = element.text_content();
+    let words: Vec<&str> = text.split_ascii_whitespace().collect();
+    if words.is_empty() {
+        return None;
+    }
+    return Some(words.join(" "));
+}
+
+// 指定したタグ名(大文字小文字は区別しない)の最初の要素の、子孫のテキストを連結した文字列を返す
+pub fn first_text_by_tag(root: &Node, tag: &str) -> Option<String> {
+    let tag = tag.to_ascii_lowercase();
+    let mut elements = Vec::new();
+    elements_by_tag(root, &tag, &mut elements);
+    return elements.first().map(|element| element.text_content());
+}
+
+// スクリプトが無効な場合に表示する<noscript>の中のテキストを、文書の順に返す
+// テキストの連続する空白は1つにまとめ、空の<noscript>は含めない
+pub fn noscript_content(root: &Node) -> Vec<String> {
+    let mut noscripts = Vec::new();
+    elements_by_tag(root, "noscript", &mut noscripts);
+    return noscripts
+        .iter()
+        .map(|noscript| collapse_whitespace(&noscript.text_content()))
+        .filter(|text| !text.is_empty())
+        .collect();
+}
+
+// 文書の題名を返す
+// 空でない<title>があればそのテキストを、なければ最初の<h1>のテキストを、連続する空白を1つにまとめて返す
+pub fn best_title(root: &Node) -> Option<String> {
+    for tag in ["title", "h1"] {
+        let mut elements = Vec::new();
+        elements_by_tag(root, tag, &mut elements);
+        let title = elements.first().map(|element| collapse_whitespace(&element.text_content()));
+        if let Some(title) = title.filter(|title| !title.is_empty()) {
+            return Some(title);
+        }
+    }
+    return None;
+}
+
+// 文書の基準URLを返す
+// href属性を持つ最初の<base>をdocument_urlを基準に解決し、ない場合や解決できない場合はdocument_urlを返す
+pub fn document_base_url(root: &Node, document_url: &Url) -> Url {
+    let mut bases = Vec::new();
+    elements_by_tag(root, "base", &mut bases);
+    if let Some(href) = bases.iter().find_map(|base| base.get_attribute("href")) {
+        if let Ok(url) = document_url.join(&href) {
+            return url;
+        }
+    }
+    return document_url.clone();
+}
+
+// target属性を持つ最初の<base>から、リンクのデフォルトのターゲット(_blankなど)を返す
+pub fn document_base_target(root: &Node) -> Option<String> {
+    let mut bases = Vec::new();
+    elements_by_tag(root, "base", &mut bases);
+    return bases.iter().find_map(|base| base.get_attribute("target"));
+}
+
+// attributeの値がvalue(大文字小文字は区別しない)の最初の<meta>のcontentを返す
+fn meta_content(root: &Node, attribute: &str, value: &str) -> Option<String> {
+    let mut metas = Vec::new();
+    elements_by_tag(root, "meta", &mut metas);
+    return metas
+        .iter()
+        .filter(|meta| meta.get_attribute(attribute).is_some_and(|v| v.trim().eq_ignore_ascii_case(value)))
+        .find_map(|meta| meta.get_attribute("content"));
+}
+
+// <meta property="og:url" content="..."> のcontentをbaseを基準に解決して返す
+pub fn og_url(root: &Node, base: &Url) -> Option<Url> {
+    let content = meta_content(root, "property", "og:url")?;
+    return base.join(&content).ok();
+}
+
+// 文書の言語を返す
+// <html>のlang属性を使い、ない場合は<meta http-equiv="Content-Language">のcontentの最初の言語を使う
+pub fn document_language(root: &Node) -> Option<String> {
+    let mut htmls = Vec::new();
+    elements_by_tag(root, "html", &mut htmls);
+    let lang = htmls.first().and_then(|html| html.get_attribute("lang")).map(|lang| lang.trim().to_string());
+    if let Some(lang) = lang.filter(|lang| !lang.is_empty()) {
+        return Some(lang);
+    }
+    let content = meta_content(root, "http-equiv", "content-language")?;
+    return content
+        .split(',')
+        .map(|language| language.trim())
+        .find(|language| !language.is_empty())
+        .map(|language| language.to_string());
+}
+
+// リンクのプレビューに使うページの情報
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageMeta {
+    title: Option<String>,
+    description: Option<String>,
+    og_title: Option<String>,
+    og_image: Option<String>,
+}
+
+impl PageMeta {
+    pub fn title(&self) -> Option<String> {
+        return self.title.clone();
+    }
+
+    pub fn description(&self) -> Option<String> {
+        return self.description.clone();
+    }
+
+    pub fn og_title(&self) -> Option<String> {
+        return self.og_title.clone();
+    }
+
+    // og:imageのcontent(URLは解決せずにそのまま返す)
+    pub fn og_image(&self) -> Option<String> {
+        return self.og_image.clone();
+    }
+}
+
+// <title>のテキスト(連続する空白は1つにまとめる)、<meta name="description">、og:title、og:imageのcontentを返す
+pub fn page_metadata(root: &Node) -> PageMeta {
+    let mut titles = Vec::new();
+    elements_by_tag(root, "title", &mut titles);
+    let title = titles.first().map(|title| collapse_whitespace(&title.text_content()));
+    return PageMeta {
+        title,
+        description: meta_content(root, "name", "description"),
+        og_title: meta_content(root, "property", "og:title"),
+        og_image: meta_content(root, "property", "og:image"),
+    };
+}
+
+// 表の行(tr)を集める(tbodyなどの有無に関わらず探し、入れ子の表の中は探さない)
+fn table_rows<'a>(node: &'a Node, rows: &mut Vec<&'a Node>) {
+    for child in node.children() {
+        match child.tag_name() {
+            Some("tr") => rows.push(child),
+            Some("table") => {}
+            _ => table_rows(child, rows),
+        }
+    }
+}
+
+// 文書中の表ごとに、行ごとのセル(td、th)のテキスト(前後の空白は取り除く)を返す
+pub fn extract_tables(root: &Node) -> Vec<Vec<Vec<String>>> {
+    let mut tables = Vec::new();
+    elements_by_tag(root, "table", &mut tables);
+
+    let mut extracted = Vec::new();
+    for table in tables {
+        let mut rows = Vec::new();
+        table_rows(table, &mut rows);
+        let rows = rows
+            .iter()
+            .map(|row| {
+                row.children()
+                    .iter()
+                    .filter(|cell| matches!(cell.tag_name(), Some("td" | "th")))
+                    .map(|cell| cell.text_content().trim().to_string())
+                    .collect()
+            })
+            .collect();
+        extracted.push(rows);
+    }
+    return extracted;
+}
+
+// 文書の順に、要素のid属性の値を集める
+fn collect_ids(node: &Node, ids: &mut Vec<String>) {
+    if let Some(id) = node.get_attribute("id") {
+        ids.push(id);
+    }
+    for child in node.children() {
+        collect_ids(child, ids);
+    }
+}
+
+// 複数の要素で使われているid属性の値を、最初に現れた順に1つずつ返す(大文字小文字は区別する)
+pub fn duplicate_ids(root: &Node) -> Vec<String> {
+    let mut ids = Vec::new();
+    collect_ids(root, &mut ids);
+
+    // idごとに使われている数を数え、2つ以上の要素で使われているidを最初に現れた順に返す
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for id in &ids {
+        *counts.entry(id.as_str()).or_insert(0) += 1;
+    }
+    let mut reported = BTreeSet::new();
+    let mut duplicates: Vec<String> = Vec::new();
+    for id in &ids {
+        if counts[id.as_str()] > 1 && reported.insert(id.as_str()) {
+            duplicates.push(id.clone());
+        }
+    }
+    return duplicates;
+}
+
+// スタイルシートの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StyleSheetKind {
+    // titleを持たず、常に適用されるスタイルシート
+    Persistent,
+    // titleを持ち、最初に選ばれているスタイルシートの組
+    Preferred,
+    // rel="alternate stylesheet" で、ユーザが選んだときだけ適用されるスタイルシートの組
+    Alternate,
+}
+
+// <link rel="stylesheet"> で指定されたスタイルシート
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StyleSheetRef {
+    href: String,
+    title: Option<String>,
+    kind: StyleSheetKind,
+}
+
+impl StyleSheetRef {
+    pub fn href(&self) -> String {
+        return self.href.clone();
+    }
+
+    pub fn title(&self) -> Option<String> {
+        return self.title.clone();
+    }
+
+    pub fn kind(&self) -> StyleSheetKind {
+        return self.kind;
+    }
+}
+
+// 文書中のスタイルシートを、relとtitleから常に適用するもの、優先するもの、代替のものに分けて文書の順に返す
+// 優先するスタイルシートの組は最初に現れたtitleのもので、それ以外のtitleを持つものは代替として扱う
+// titleのない代替のスタイルシートは適用されないため含めない
+pub fn stylesheet_sets(root: &Node) -> Vec<StyleSheetRef> {
+    let mut links = Vec::new();
+    elements_by_tag(root, "link", &mut links);
+
+    let mut preferred_title: Option<String> = None;
+    let mut sheets = Vec::new();
+    for link in links {
+        let rel = link.get_attribute("rel").unwrap_or_default();
+        let has_rel = |name: &str| rel.split_ascii_whitespace().any(|r| r.eq_ignore_ascii_case(name));
+        if !has_rel("stylesheet") {
+            continue;
+        }
+        let href = match link.get_attribute("href") {
+            Some(href) => href,
+            None => continue,
+        };
+        let title = link.get_attribute("title").filter(|title| !title.is_empty());
+        let kind = match &title {
+            None if has_rel("alternate") => continue,
+            None => StyleSheetKind::Persistent,
+            Some(_) if has_rel("alternate") => StyleSheetKind::Alternate,
+            Some(title) => match &preferred_title {
+                Some(preferred) if preferred != title => StyleSheetKind::Alternate,
+                _ => {
+                    preferred_title = Some(title.clone());
+                    StyleSheetKind::Preferred
+                }
+            },
+        };
+        sheets.push(StyleSheetRef { href, title, kind });
+    }
+    return sheets;
+}
+
+// 文書の順に、全てのイベントハンドラ属性(onclick、onloadなど)をタグ名、属性名、値の組で返す
+pub fn inline_event_handlers(root: &Node) -> Vec<(String, String, String)> {
+    let mut handlers = Vec::new();
+    for element in find_elements(root, |_| true) {
+        if let NodeKind::Element { tag, attributes } = element.kind() {
+            for attribute in attributes.iter().filter(|attribute| is_event_handler_attribute(&attribute.name())) {
+                handlers.push((tag.clone(), attribute.name(), attribute.value()));
+            }
+        }
+    }
+    return handlers;
+}
+
+// 文書から読み込むサブリソース(スクリプト、スタイルシート、画像)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Resource {
+    tag: String,
+    url: Url,
+    crossorigin: Option<String>,
+    referrerpolicy: Option<String>,
+}
+
+impl Resource {
+    pub fn tag(&self) -> String {
+        return self.tag.clone();
+    }
+
+    pub fn url(&self) -> Url {
+        return self.url.clone();
+    }
+
+    // crossorigin属性の値(属性のみで値がない場合は空文字列)
+    pub fn crossorigin(&self) -> Option<String> {
+        return self.crossorigin.clone();
+    }
+
+    pub fn referrerpolicy(&self) -> Option<String> {
+        return self.referrerpolicy.clone();
+    }
+}
+
+// リソースのURLを持つ属性を返す(<link>はrel="stylesheet"のもののみ)
+fn resource_url_attribute(element: &Node) -> Option<String> {
+    match element.tag_name()? {
+        "script" | "img" => element.get_attribute("src"),
+        "link" => {
+            let is_stylesheet = element.get_attribute("rel").is_some_and(|rel| {
+                rel.split_ascii_whitespace().any(|r| r.eq_ignore_ascii_case("stylesheet"))
+            });
+            if is_stylesheet {
+                element.get_attribute("href")
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+fn collect_resources(node: &Node, base: &Url, resources: &mut Vec<Resource>) {
+    if let Some(Ok(url)) = resource_url_attribute(node).map(|href| base.join(&href)) {
+        resources.push(Resource {
+            tag: node.tag_name().unwrap_or_default().to_string(),
+            url,
+            crossorigin: node.get_attribute("crossorigin"),
+            referrerpolicy: node.get_attribute("referrerpolicy"),
+        });
+    }
+    for child in node.children() {
+        collect_resources(child, base, resources);
+    }
+}
+
+// 文書の順に、読み込むリソースのURLをbaseを基準に解決して、crossorigin属性とreferrerpolicy属性と共に返す
+pub fn extract_resources(root: &Node, base: &Url) -> Vec<Resource> {
+    let mut resources = Vec::new();
+    collect_resources(root, base, &mut resources);
+    return resources;
+}
+
+// 文書の順に、リンク(<a>、<link>)、画像、スクリプト、スタイルシートなどのURLとsrcsetの候補をbaseを基準に解決して返す
+// キャッシュキーが同じURLは最初に現れたもののみを残し、解決できないURLは読み捨てる
+pub fn all_resource_urls(root: &Node, base: &Url) -> Vec<Url> {
+    let mut urls: Vec<Url> = Vec::new();
+    let mut seen = BTreeSet::new();
+    let mut push = |url: Url| {
+        if seen.insert(url.cache_key()) {
+            urls.push(url);
+        }
+    };
+    for element in find_elements(root, |_| true) {
+        // extract_resourcesが読み込むリソースに加えて、リンクと埋め込みのメディアのURLも含める
+        let value = resource_url_attribute(element).or_else(|| match element.tag_name() {
+            Some("a" | "link") => element.get_attribute("href"),
+            Some("source" | "iframe" | "audio" | "video" | "embed") => element.get_attribute("src"),
+            _ => None,
+        });
+        if let Some(Ok(url)) = value.map(|value| base.join(&value)) {
+            push(url);
+        }
+        if let Some(srcset) = element.get_attribute("srcset") {
+            for (url, _) in parse_srcset(&srcset, base) {
+                push(url);
+            }
+        }
+    }
+    return urls;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn element(tag: &str, children: Vec<Node>) -> Node {
+        let mut node = Node::new(NodeKind::Element { tag: tag.to_string(), attributes: Vec::new() });
+        for child in children {
+            node.append_child(child);
+        }
+        return node;
+    }
+
+    fn text(s: &str) -> Node {
+        return Node::new(NodeKind::Text(s.to_string()));
+    }
+
+    fn document(children: Vec<Node>) -> Node {
+        let mut node = Node::new(NodeKind::Document);
+        for child in children {
+            node.append_child(child);
+        }
+        return node;
+    }
+
+    // 補われた<html>の子の<head>と<body>の子として要素を持つ文書
+    fn html_document(head: Vec<Node>, body: Vec<Node>) -> Node {
+        return document(vec![element("html", vec![element("head", head), element("body", body)])]);
+    }
+
+    fn body(dom: &Node) -> &Node {
+        return split_head_body(dom).1.expect("document should have a body");
+    }
+
+    // 閉じられていない li が次の li で閉じられる場合
+    #[test]
+    fn test_build_dom_list() {
+        let dom = build_dom(HtmlTokenizer::new("<ul><li>a<li>b</ul>".to_string()));
+        let expected = html_document(vec![], vec![element("ul", vec![
+            element("li", vec![text("a")]),
+            element("li", vec![text("b")]),
+        ])]);
+        assert_eq!(dom, expected);
+    }
+
+    // 終了タグが一番上の要素と一致しない場合、一致する祖先まで閉じる
+    #[test]
+    fn test_build_dom_mismatched_end_tag() {
+        let dom = build_dom(HtmlTokenizer::new("<div><span>ab</div>c</span>".to_string()));
+        let expected = html_document(vec![], vec![
+            element("div", vec![element("span", vec![text("ab")])]),
+            text("c"),
+        ]);
+        assert_eq!(dom, expected);
+    }
+
+    // 閉じられていない表をパースした際の挿入モードの遷移
+    #[test]
+    fn test_insertion_mode_trace() {
+        let mut parser = HtmlParser::new(HtmlTokenizer::new("<table><tr><td>x".to_string()));
+        parser.set_trace_insertion_modes(true);
+        let dom = parser.construct_tree();
+        let expected = [
+            InsertionMode::Initial,
+            InsertionMode::BeforeHtml,
+            InsertionMode::BeforeHead,
+            InsertionMode::InHead,
+            InsertionMode::AfterHead,
+            InsertionMode::InBody,
+            InsertionMode::InTable,
+            InsertionMode::InRow,
+            InsertionMode::InCell,
+        ];
+        assert_eq!(parser.insertion_mode_trace(), expected);
+        assert_eq!(dom, html_document(vec![], vec![element("table", vec![element("tr", vec![element("td", vec![text("x")])])])]));
+
+        // 明示的な<head>、<title>、</body>を含む文書の場合
+        let html = "<!DOCTYPE html><html><head><title>t</title></head><body><p>a</p></body>\n</html>";
+        let mut parser = HtmlParser::new(HtmlTokenizer::new(html.to_string()));
+        parser.set_trace_insertion_modes(true);
+        parser.construct_tree();
+        let expected = [
+            InsertionMode::Initial,
+            InsertionMode::BeforeHtml,
+            InsertionMode::BeforeHead,
+            InsertionMode::InHead,
+            InsertionMode::Text,
+            InsertionMode::InHead,
+            InsertionMode::AfterHead,
+            InsertionMode::InBody,
+            InsertionMode::AfterBody,
+        ];
+        assert_eq!(parser.insertion_mode_trace(), expected);
+
+        // 記録を有効にしていない場合は空
+        let mut parser = HtmlParser::new(HtmlTokenizer::new("<p>a</p>".to_string()));
+        parser.construct_tree();
+        assert!(parser.insertion_mode_trace().is_empty());
+    }
+
+    // hidden属性を持つ子孫のテキストを含めない場合
+    #[test]
+    fn test_text_content_hidden() {
+        let dom = build_dom(HtmlTokenizer::new("<h1>Visible <span hidden>secret <b>x</b></span>title</h1>".to_string()));
+        let h1 = &body(&dom).children()[0];
+        assert_eq!(h1.text_content(), "Visible title".to_string());
+        assert_eq!(accessible_name(h1), Some("Visible title".to_string()));
+        assert_eq!(best_title(&dom), Some("Visible title".to_string()));
+    }
+
+    // アンカー要素のhrefを取得し、テキストノードではNoneを返す場合
+    #[test]
+    fn test_get_attribute() {
+        let dom = build_dom(HtmlTokenizer::new("<a HREF=\"/next\">next</a>".to_string()));
+        let anchor = &body(&dom).children()[0];
+        assert_eq!(anchor.get_attribute("href"), Some("/next".to_string()));
+        assert_eq!(anchor.get_attribute("title"), None);
+        assert_eq!(anchor.children()[0].get_attribute("href"), None);
+    }
+
+    // <html>より前の空白とコメントがあり、<html>が省略されている場合
+    #[test]
+    fn test_build_dom_before_html() {
+        let dom = build_dom(HtmlTokenizer::new("\n<!-- c -->\n<head></head><body>x</body>".to_string()));
+        let expected = document(vec![
+            Node::new(NodeKind::Comment(" c ".to_string())),
+            element("html", vec![element("head", vec![]), element("body", vec![text("x")])]),
+        ]);
+        assert_eq!(dom, expected);
+
+        // 明示した<html>の属性は残し、空の文書にも<html>を補う
+        let dom = build_dom(HtmlTokenizer::new("  <html lang=\"ja\"><p>a</p></html>".to_string()));
+        assert_eq!(dom.children().len(), 1);
+        assert_eq!(dom.children()[0].get_attribute("lang"), Some("ja".to_string()));
+        assert_eq!(build_dom(HtmlTokenizer::new("".to_string())), html_document(vec![], vec![]));
+    }
+
+    // <head>と<body>が省略されている場合、<head>に入る要素の後に<body>を補う
+    #[test]
+    fn test_split_head_body() {
+        let dom = build_dom(HtmlTokenizer::new("<title>t</title><p>x</p>".to_string()));
+        let (head, body) = split_head_body(&dom);
+        assert_eq!(head, Some(&element("head", vec![element("title", vec![text("t")])])));
+        assert_eq!(body, Some(&element("body", vec![element("p", vec![text("x")])])));
+
+        // 明示した<head>と<body>の属性は残し、</body>の後の要素も<body>に入れる
+        let dom = build_dom(HtmlTokenizer::new(
+            "<html><head>\n<meta charset=\"utf-8\">\n</head>\n<body class=\"a\"><p>x</p></body><p>y</p></html>".to_string(),
+        ));
+        let (head, body) = split_head_body(&dom);
+        let head = head.expect("document should have a head");
+        let body = body.expect("document should have a body");
+        assert_eq!(head.children().iter().filter(|n| n.tag_name() == Some("meta")).count(), 1);
+        assert_eq!(body.get_attribute("class"), Some("a".to_string()));
+        assert_eq!(body.children().len(), 2);
+        assert_eq!(split_head_body(&dom.children()[0]), (Some(head), Some(body)));
+
+        assert_eq!(split_head_body(&text("x")), (None, None));
+    }
+
+    // RSSとAtomのフィードへのリンクを持つ場合
+    #[test]
+    fn test_feed_links() {
+        let html = "<head><link rel=\"alternate\" type=\"application/rss+xml\" href=\"/rss.xml\">\
+            <link rel=\"stylesheet\" href=\"a.css\">\
+            <link rel=\"Alternate\" type=\"Application/Atom+XML\" href=\"https://feeds.example.com/atom\">\
+            <link rel=\"alternate\" hreflang=\"en\" href=\"/en/\"></head>";
+        let dom = build_dom(HtmlTokenizer::new(html.to_string()));
+        let base = Url::new("http://example.com/blog/".to_string()).parse().expect("failed to parse url");
+        let expected = vec![
+            Url::new("http://example.com/rss.xml".to_string()).parse().expect("failed to parse url"),
+            Url::new("https://feeds.example.com/atom".to_string()).parse().expect("failed to parse url"),
+        ];
+        assert_eq!(feed_links(&dom, &base), expected);
+    }
+
+    // accept-charset属性に複数の文字コードがある場合
+    #[test]
+    fn test_form_accept_charset() {
+        let dom = build_dom(HtmlTokenizer::new("<form accept-charset=\"UTF-8 ISO-8859-1\"></form><form></form>".to_string()));
+        assert_eq!(form_accept_charset(&body(&dom).children()[0]), Some("UTF-8".to_string()));
+        assert_eq!(form_accept_charset(&body(&dom).children()[1]), None);
+        assert_eq!(form_accept_charset(&dom), None);
+    }
+
+    // actionとmethodを明示した<form>の場合
+    #[test]
+    fn test_default_form_action() {
+        let document_url = Url::new("http://example.com/a/page.html".to_string()).parse().expect("failed to parse url");
+        let dom = build_dom(HtmlTokenizer::new(
+            "<form action=\"/search\" method=\"post\"></form><form action=\"other\"></form>".to_string(),
+        ));
+        let expected = Url::new("http://example.com/search".to_string()).parse().expect("failed to parse url");
+        assert_eq!(default_form_action(&dom, &document_url), Some((expected, "POST".to_string())));
+    }
+
+    // actionとmethodを省略した<form>と、<form>がない場合
+    #[test]
+    fn test_default_form_action_defaults() {
+        let document_url = Url::new("http://example.com/a/page.html".to_string()).parse().expect("failed to parse url");
+        let dom = build_dom(HtmlTokenizer::new("<form><input name=\"q\"></form>".to_string()));
+        assert_eq!(default_form_action(&dom, &document_url), Some((document_url.clone(), "GET".to_string())));
+
+        let dom = build_dom(HtmlTokenizer::new("<form action=\"\" method=\"put\"></form>".to_string()));
+        assert_eq!(default_form_action(&dom, &document_url), Some((document_url.clone(), "GET".to_string())));
+
+        let dom = build_dom(HtmlTokenizer::new("<p>no form</p>".to_string()));
+        assert_eq!(default_form_action(&dom, &document_url), None);
+    }
+
+    // <input type="file">を含む<form>と、multipart/form-dataで送信する<form>の場合
+    #[test]
+    fn test_has_file_upload() {
+        let dom = build_dom(HtmlTokenizer::new("<form><div><input TYPE=\"File\" name=\"f\"></div></form>".to_string()));
+        assert!(has_file_upload(&dom));
+        let dom = build_dom(HtmlTokenizer::new("<form enctype=\"Multipart/Form-Data\"><input name=\"q\"></form>".to_string()));
+        assert!(has_file_upload(&dom));
+    }
+
+    // ファイルを送信する<form>がない場合
+    #[test]
+    fn test_has_file_upload_without_file_input() {
+        let dom = build_dom(HtmlTokenizer::new("<form><input type=\"text\" name=\"q\"></form><input type=\"file\">".to_string()));
+        assert!(!has_file_upload(&dom));
+    }
+
+    // viewportのmetaタグがある場合
+    #[test]
+    fn test_viewport_config() {
+        let html = "<meta charset=\"utf-8\"><meta name=\"Viewport\" content=\"width=device-width, initial-scale=1\">";
+        let dom = build_dom(HtmlTokenizer::new(html.to_string()));
+        let expected = vec![
+            ("width".to_string(), "device-width".to_string()),
+            ("initial-scale".to_string(), "1".to_string()),
+        ];
+        assert_eq!(viewport_config(&dom), Some(expected));
+
+        let dom = build_dom(HtmlTokenizer::new("<meta name=\"description\" content=\"x=1\">".to_string()));
+        assert_eq!(viewport_config(&dom), None);
+    }
+
+    // robotsのmetaタグの指示を , で区切って小文字にする場合
+    #[test]
+    fn test_robots_directives() {
+        let dom = build_dom(HtmlTokenizer::new(
+            "<meta name=\"robots\" content=\"noindex, nofollow\"><meta name=\"ROBOTS\" content=\"NoArchive,noindex,\">".to_string(),
+        ));
+        assert_eq!(
+            robots_directives(&dom),
+            vec!["noindex".to_string(), "nofollow".to_string(), "noarchive".to_string()]
+        );
+
+        let dom = build_dom(HtmlTokenizer::new("<meta name=\"description\" content=\"noindex\">".to_string()));
+        assert!(robots_directives(&dom).is_empty());
+    }
+
+    // ブロック要素、インライン要素、リストを含む文書を描画する場合
+    #[test]
+    fn test_render_text() {
+        let html = "<html><head><title>t</title><style>p{}</style></head><body>\
+            <h1>Title</h1>\n<p>Hello,   <b>bold</b> <a href=\"/\">link</a></p>\
+            <ul>\n<li>one<li>two</ul><script>alert(1)</script><div>end</div></body></html>";
+        let dom = build_dom(HtmlTokenizer::new(html.to_string()));
+        assert_eq!(render_text(&dom), "Title\nHello, bold link\n- one\n- two\nend");
+    }
+
+    // 入れ子の要素を字下げし、短いテキストは要素と同じ行にまとめる場合
+    #[test]
+    fn test_pretty_print() {
+        let html = "<html><body><div class=\"a&b\"><h1>Title</h1><p>x &lt; y <b>bold</b></p><br><input disabled><p></p>\
+            <!--note--><p>This paragraph is long enough to be written on its own line.</p></div></body></html>";
+        let dom = build_dom(HtmlTokenizer::new(html.to_string()));
+        let expected = "<html>\n\
+            \x20 <head></head>\n\
+            \x20 <body>\n\
+            \x20   <div class=\"a&amp;b\">\n\
+            \x20     <h1>Title</h1>\n\
+            \x20     <p>\n\
+            \x20       x &lt; y\n\
+            \x20       <b>bold</b>\n\
+            \x20     </p>\n\
+            \x20     <br>\n\
+            \x20     <input disabled>\n\
+            \x20     <p></p>\n\
+            \x20     <!--note-->\n\
+            \x20     <p>\n\
+            \x20       This paragraph is long enough to be written on its own line.\n\
+            \x20     </p>\n\
+            \x20   </div>\n\
+            \x20 </body>\n\
+            </html>\n";
+        assert_eq!(pretty_print(&dom, 2), expected);
+    }
+
+    // <br> で改行し、<wbr> では改行しない場合
+    #[test]
+    fn test_render_text_line_breaks() {
+        let dom = build_dom(HtmlTokenizer::new("a<br>b<wbr>c".to_string()));
+        assert_eq!(render_text(&dom), "a\nbc");
+
+        let dom = build_dom(HtmlTokenizer::new("<p>one<br/> two</p>".to_string()));
+        assert_eq!(render_text(&dom), "one\ntwo");
+    }
+
+    // hidden属性を持つ要素は描画しない場合
+    #[test]
+    fn test_render_text_hidden() {
+        let dom = build_dom(HtmlTokenizer::new("<p>visible</p><p hidden>secret</p><span hidden>x<b>y</b></span>".to_string()));
+        assert_eq!(render_text(&dom), "visible");
+    }
+
+    // aria-label、alt、テキストの順に名前を探す場合
+    #[test]
+    fn test_accessible_name() {
+        let dom = build_dom(HtmlTokenizer::new("<img alt=\"logo\"><button aria-label=\"close\">X</button><span> some\n <b>text</b> </span><img>".to_string()));
+        let children = body(&dom).children();
+        assert_eq!(accessible_name(&children[0]), Some("logo".to_string()));
+        assert_eq!(accessible_name(&children[1]), Some("close".to_string()));
+        assert_eq!(accessible_name(&children[2]), Some("some text".to_string()));
+        assert_eq!(accessible_name(&children[3]), None);
+    }
+
+    // <base>がhrefとtargetを持つ場合
+    #[test]
+    fn test_document_base() {
+        let dom = build_dom(HtmlTokenizer::new("<head><base href=\"/x/\" target=\"_blank\"></head><a href=\"y\">y</a>".to_string()));
+        let document_url = Url::new("http://example.com/a/b.html".to_string()).parse().expect("failed to parse url");
+        let expected = Url::new("http://example.com/x/".to_string()).parse().expect("failed to parse url");
+        assert_eq!(document_base_target(&dom), Some("_blank".to_string()));
+        assert_eq!(document_base_url(&dom, &document_url), expected);
+    }
+
+    // <base>がない場合
+    #[test]
+    fn test_document_base_without_base() {
+        let dom = build_dom(HtmlTokenizer::new("<p>x</p>".to_string()));
+        let document_url = Url::new("http://example.com/a/b.html".to_string()).parse().expect("failed to parse url");
+        assert_eq!(document_base_target(&dom), None);
+        assert_eq!(document_base_url(&dom, &document_url), document_url);
+    }
+
+    // 2つのh1がある場合、最初のh1のテキストを返す
+    #[test]
+    fn test_first_text_by_tag() {
+        let dom = build_dom(HtmlTokenizer::new("<h1>First <em>title</em></h1><h1>Second</h1>".to_string()));
+        assert_eq!(first_text_by_tag(&dom, "h1"), Some("First title".to_string()));
+        assert_eq!(first_text_by_tag(&dom, "H1"), Some("First title".to_string()));
+        assert_eq!(first_text_by_tag(&dom, "h2"), None);
+    }
+
+    // <head>と<body>の<noscript>のテキストを返す場合
+    #[test]
+    fn test_noscript_content() {
+        let dom = build_dom(HtmlTokenizer::new(
+            "<noscript>JS required</noscript><p>x</p><noscript>\n  Please <b>enable</b>\n JavaScript </noscript><noscript> </noscript>".to_string(),
+        ));
+        assert_eq!(noscript_content(&dom), vec!["JS required".to_string(), "Please enable JavaScript".to_string()]);
+        assert!(noscript_content(&build_dom(HtmlTokenizer::new("<p>x</p>".to_string()))).is_empty());
+    }
+
+    // <title>がある場合、<h1>のみの場合、どちらもない場合
+    #[test]
+    fn test_best_title() {
+        let dom = build_dom(HtmlTokenizer::new("<head><title> Page\n title </title></head><body><h1>Heading</h1></body>".to_string()));
+        assert_eq!(best_title(&dom), Some("Page title".to_string()));
+
+        let dom = build_dom(HtmlTokenizer::new("<head><title> </title></head><body><h1>First <b>heading</b></h1><h1>Second</h1></body>".to_string()));
+        assert_eq!(best_title(&dom), Some("First heading".to_string()));
+
+        let dom = build_dom(HtmlTokenizer::new("<p>no title</p>".to_string()));
+        assert_eq!(best_title(&dom), None);
+    }
+
+    // tbodyのない2x2の表と、theadとtbodyのある表の場合
+    #[test]
+    fn test_extract_tables() {
+        let html = "<table><tr><th>a</th><th>b</th></tr>\n<tr><td> 1 </td><td>2</td></tr></table>\
+            <table><thead><tr><th>x</th></tr></thead><tbody><tr><td>y</td></tr></tbody></table>";
+        let dom = build_dom(HtmlTokenizer::new(html.to_string()));
+        let expected = vec![
+            vec![vec!["a".to_string(), "b".to_string()], vec!["1".to_string(), "2".to_string()]],
+            vec![vec!["x".to_string()], vec!["y".to_string()]],
+        ];
+        assert_eq!(extract_tables(&dom), expected);
+    }
+
+    // <html>のlang属性、Content-Languageのmetaタグ、どちらもない場合
+    #[test]
+    fn test_document_language() {
+        let dom = build_dom(HtmlTokenizer::new("<html lang=\"ja\"><p>x</p></html>".to_string()));
+        assert_eq!(document_language(&dom), Some("ja".to_string()));
+
+        let dom = build_dom(HtmlTokenizer::new("<meta http-equiv=\"Content-Language\" content=\"en-US, fr\"><p>x</p>".to_string()));
+        assert_eq!(document_language(&dom), Some("en-US".to_string()));
+
+        let dom = build_dom(HtmlTokenizer::new("<html><p>x</p></html>".to_string()));
+        assert_eq!(document_language(&dom), None);
+    }
+
+    // <title>、descriptionのmetaタグ、og:imageのmetaタグがある場合
+    #[test]
+    fn test_page_metadata() {
+        let dom = build_dom(HtmlTokenizer::new(
+            "<title>  Saba\n browser </title><meta name=\"Description\" content=\"A toy browser\">\
+             <meta property=\"og:image\" content=\"/ogp.png\"><p>body</p>"
+                .to_string(),
+        ));
+        let meta = page_metadata(&dom);
+        assert_eq!(meta.title(), Some("Saba browser".to_string()));
+        assert_eq!(meta.description(), Some("A toy browser".to_string()));
+        assert_eq!(meta.og_title(), None);
+        assert_eq!(meta.og_image(), Some("/ogp.png".to_string()));
+
+        let meta = page_metadata(&build_dom(HtmlTokenizer::new("<p>x</p>".to_string())));
+        assert_eq!(meta.title(), None);
+        assert_eq!(meta.description(), None);
+    }
+
+    // og:urlのmetaタグがある場合とない場合
+    #[test]
+    fn test_og_url() {
+        let base = Url::new("http://example.com/a/".to_string()).parse().expect("failed to parse url");
+        let dom = build_dom(HtmlTokenizer::new("<meta property=\"og:title\" content=\"t\"><meta property=\"og:url\" content=\"/post/1\">".to_string()));
+        let expected = Url::new("http://example.com/post/1".to_string()).parse().expect("failed to parse url");
+        assert_eq!(og_url(&dom, &base), Some(expected));
+
+        let dom = build_dom(HtmlTokenizer::new("<meta property=\"og:title\" content=\"t\">".to_string()));
+        assert_eq!(og_url(&dom, &base), None);
+    }
+
+    // 常に適用するもの、優先するもの、代替のスタイルシートが混ざっている場合
+    #[test]
+    fn test_stylesheet_sets() {
+        let dom = build_dom(HtmlTokenizer::new(
+            "<link rel=\"stylesheet\" href=\"base.css\">\
+             <link rel=\"stylesheet\" href=\"light.css\" title=\"Light\">\
+             <link rel=\"alternate stylesheet\" href=\"dark.css\" title=\"Dark\">\
+             <link rel=\"alternate stylesheet\" href=\"untitled.css\">\
+             <link rel=\"stylesheet\" href=\"other.css\" title=\"Other\">"
+                .to_string(),
+        ));
+        let sheets = stylesheet_sets(&dom);
+        let summary: Vec<(String, Option<String>, StyleSheetKind)> =
+            sheets.iter().map(|sheet| (sheet.href(), sheet.title(), sheet.kind())).collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("base.css".to_string(), None, StyleSheetKind::Persistent),
+                ("light.css".to_string(), Some("Light".to_string()), StyleSheetKind::Preferred),
+                ("dark.css".to_string(), Some("Dark".to_string()), StyleSheetKind::Alternate),
+                ("other.css".to_string(), Some("Other".to_string()), StyleSheetKind::Alternate),
+            ]
+        );
+    }
+
+    // 要素の間の空白だけが異なるツリーの場合
+    #[test]
+    fn test_tree_eq_ignoring_whitespace() {
+        let a = build_dom(HtmlTokenizer::new("<ul><li>a  b</li><li>c</li></ul>".to_string()));
+        let b = build_dom(HtmlTokenizer::new("\n<ul>\n  <li> a\nb </li>\n  <li>c</li>\n</ul>\n".to_string()));
+        assert_ne!(a, b);
+        assert!(tree_eq_ignoring_whitespace(&a, &b));
+
+        let c = build_dom(HtmlTokenizer::new("<ul><li>a b</li><li>d</li></ul>".to_string()));
+        let d = build_dom(HtmlTokenizer::new("<ol><li>a b</li><li>c</li></ol>".to_string()));
+        assert!(!tree_eq_ignoring_whitespace(&a, &c));
+        assert!(!tree_eq_ignoring_whitespace(&a, &d));
+    }
+
+    // 要素の間に空白のテキストとコメントがある場合
+    #[test]
+    fn test_element_children() {
+        let dom = build_dom(HtmlTokenizer::new("<ul> <li>a</li> <!--c--> <li>b</li> </ul>".to_string()));
+        let ul = &body(&dom).children()[0];
+        let children = element_children(ul);
+        assert_eq!(children, vec![&element("li", vec![text("a")]), &element("li", vec![text("b")])]);
+        assert!(element_children(&text("x")).is_empty());
+    }
+
+    // 空でないid属性を持つ要素を集める場合
+    #[test]
+    fn test_find_elements() {
+        let dom = build_dom(HtmlTokenizer::new(
+            "<div id=\"a\"><p id=\"\">x</p><span id=\"b\">y<b id=\"c\"></b></span></div><p>z</p>".to_string(),
+        ));
+        let found = find_elements(&dom, |element| element.get_attribute("id").is_some_and(|id| !id.is_empty()));
+        let ids: Vec<String> = found.iter().filter_map(|element| element.get_attribute("id")).collect();
+        assert_eq!(ids, vec!["a".to_string(), "b".to_string(), "c".to_string()]);
+
+        assert!(find_elements(&dom, |element| element.tag_name() == Some("table")).is_empty());
+    }
+
+    // 2つの要素が同じidを持つ場合(大文字小文字が違うidは別のidとして扱う)
+    #[test]
+    fn test_duplicate_ids() {
+        let dom = build_dom(HtmlTokenizer::new(
+            "<div id=\"x\"><p id=\"y\">a</p><p id=\"X\">b</p></div><span id=\"x\"></span>".to_string(),
+        ));
+        assert_eq!(duplicate_ids(&dom), vec!["x".to_string()]);
+
+        let dom = build_dom(HtmlTokenizer::new("<p id=\"a\"></p><p id=\"b\"></p>".to_string()));
+        assert!(duplicate_ids(&dom).is_empty());
+    }
+
+    // onclick属性とonerror属性を持つ要素がある場合
+    #[test]
+    fn test_inline_event_handlers() {
+        let dom = build_dom(HtmlTokenizer::new(
+            "<body onLoad=\"init()\"><button onclick=\"go()\" class=\"on\">go</button><img src=\"x\" ONERROR=\"fail()\"><p on=\"2\">p</p></body>".to_string(),
+        ));
+        let expected = vec![
+            ("body".to_string(), "onload".to_string(), "init()".to_string()),
+            ("button".to_string(), "onclick".to_string(), "go()".to_string()),
+            ("img".to_string(), "onerror".to_string(), "fail()".to_string()),
+        ];
+        assert_eq!(inline_event_handlers(&dom), expected);
+    }
+
+    // 重複するリソースがある場合、最初に現れた順に1つずつ返す
+    #[test]
+    fn test_all_resource_urls() {
+        let base = Url::new("http://example.com/a/".to_string()).parse().expect("failed to parse url");
+        let dom = build_dom(HtmlTokenizer::new(
+            "<link rel=\"stylesheet\" href=\"s.css\"><script src=\"/a/app.js\"></script>\
+             <img src=\"i.png\" srcset=\"i.png 1x, i2.png 2x\"><a href=\"http://EXAMPLE.com/a/s.css#x\">css</a>\
+             <a href=\"next.html\">next</a><script src=\"app.js\"></script>"
+                .to_string(),
+        ));
+        let urls: Vec<String> = all_resource_urls(&dom, &base).iter().map(|url| url.cache_key()).collect();
+        let expected = vec![
+            "http://example.com/a/s.css".to_string(),
+            "http://example.com/a/app.js".to_string(),
+            "http://example.com/a/i.png".to_string(),
+            "http://example.com/a/i2.png".to_string(),
+            "http://example.com/a/next.html".to_string(),
+        ];
+        assert_eq!(urls, expected);
+    }
+
+    // crossorigin属性とreferrerpolicy属性を持つリソースと持たないリソースの場合
+    #[test]
+    fn test_extract_resources() {
+        let base = Url::new("http://example.com/a/".to_string()).parse().expect("failed to parse url");
+        let dom = build_dom(HtmlTokenizer::new(
+            "<link rel=\"icon\" href=\"f.ico\"><link rel=\"stylesheet\" href=\"s.css\" referrerpolicy=\"no-referrer\"><script src=\"x\" crossorigin=\"anonymous\"></script><img src=\"/i.png\" crossorigin>".to_string(),
+        ));
+        let resources = extract_resources(&dom, &base);
+        assert_eq!(resources.len(), 3);
+
+        assert_eq!(resources[0].tag(), "link".to_string());
+        assert_eq!(resources[0].crossorigin(), None);
+        assert_eq!(resources[0].referrerpolicy(), Some("no-referrer".to_string()));
+
+        assert_eq!(resources[1].tag(), "script".to_string());
+        let expected = Url::new("http://example.com/a/x".to_string()).parse().expect("failed to parse url");
+        assert_eq!(resources[1].url(), expected);
+        assert_eq!(resources[1].crossorigin(), Some("anonymous".to_string()));
+        assert_eq!(resources[1].referrerpolicy(), None);
+
+        assert_eq!(resources[2].tag(), "img".to_string());
+        assert_eq!(resources[2].crossorigin(), Some("".to_string()));
+    }
+
+    // 空要素、自己終了タグ、コメント、属性を持つ場合
+    #[test]
+    fn test_build_dom_void_and_comment() {
+        let dom = build_dom(HtmlTokenizer::new("<!DOCTYPE html><p class=\"x\">a<br>b<img/><!--c--></p>".to_string()));
+        assert_eq!(dom.children().len(), 1);
+        let p = &body(&dom).children()[0];
+        assert_eq!(p.tag_name(), Some("p"));
+        assert_eq!(p.get_attribute("CLASS"), Some("x".to_string()));
+        let expected = vec![
+            text("a"),
+            element("br", vec![]),
+            text("b"),
+            element("img", vec![]),
+            Node::new(NodeKind::Comment("c".to_string())),
+        ];
+        assert_eq!(p.children(), expected.as_slice());
+    }
+}
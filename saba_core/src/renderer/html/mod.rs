@@ -1,2 +1,460 @@
 pub mod attribute;
+pub mod dom;
 pub mod token;
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use crate::renderer::html::attribute::{is_event_handler_attribute, Attribute};
+use crate::renderer::html::token::{HtmlToken, HtmlTokenizer};
+
+// 終了タグを持たない空要素
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track", "wbr",
+];
+
+// タグ名が空要素かどうかを判定する(大文字小文字は区別しない)
+pub fn is_void_element(tag: &str) -> bool {
+    return VOID_ELEMENTS.iter().any(|v| v.eq_ignore_ascii_case(tag));
+}
+
+// HTMLに指定した名前(大文字小文字は区別しない)の開始タグが含まれているかを判定する
+// 最初に見つかった時点でトークナイズを打ち切る
+pub fn contains_tag(html: &str, tag: &str) -> bool {
+    let mut tokenizer = HtmlTokenizer::new(html.to_string());
+    return tokenizer.skip_to_start_tag(tag).is_some();
+}
+
+// 指定した名前(大文字小文字は区別しない)の開始タグを全て探し、入力中の位置(< のバイト単位の位置)と属性を返す
+pub fn find_all_start_tags(html: &str, name: &str) -> Vec<(usize, Vec<Attribute>)> {
+    let mut found = Vec::new();
+    let mut tokenizer = HtmlTokenizer::from_str(html);
+    while let Some(token) = tokenizer.next() {
+        if let HtmlToken::StartTag { tag, attributes, .. } = token {
+            if tag.eq_ignore_ascii_case(name) {
+                found.push((tokenizer.last_start_tag_offset(), attributes));
+            }
+        }
+    }
+    return found;
+}
+
+// <head>の部分のトークンのみを返す
+// </head>の終了タグ(結果に含める)か、最初の<body>の開始タグ(結果に含めない)でトークナイズを打ち切る
+pub fn head_tokens(html: &str) -> Vec<HtmlToken> {
+    let mut tokens = Vec::new();
+    for token in HtmlTokenizer::from_str(html) {
+        match token {
+            HtmlToken::StartTag { ref tag, .. } if tag == "body" => break,
+            HtmlToken::EndTag { ref tag } if tag == "head" => {
+                tokens.push(token);
+                break;
+            }
+            _ => tokens.push(token),
+        }
+    }
+    return tokens;
+}
+
+// 2つのトークン列が最初に異なった位置と、それぞれのトークン(トークン列が先に終わった場合はNone)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenDiff {
+    pub index: usize,
+    pub expected: Option<HtmlToken>,
+    pub actual: Option<HtmlToken>,
+}
+
+// aとbをトークナイズして比較し、最初に異なったトークンを返す
+// 1つ異なると以降の位置はずれるため、最初の違いのみを返す(同じ場合は空)
+pub fn diff_tokens(a: &str, b: &str) -> Vec<TokenDiff> {
+    let mut expected = HtmlTokenizer::from_str(a);
+    let mut actual = HtmlTokenizer::from_str(b);
+    let mut index = 0;
+    loop {
+        match (expected.next(), actual.next()) {
+            (None, None) => return Vec::new(),
+            (e, a) if e != a => return alloc::vec![TokenDiff { index, expected: e, actual: a }],
+            _ => index += 1,
+        }
+    }
+}
+
+// トークン列の開始タグと終了タグの対応が取れているかをスタックを使って検証する
+// 対応が取れていない場合は、問題のあったタグの一覧を返す
+pub fn check_tag_balance<I: Iterator<Item = HtmlToken>>(iter: I) -> Result<(), Vec<String>> {
+    let mut stack: Vec<String> = Vec::new();
+    let mut errors = Vec::new();
+    for token in iter {
+        match token {
+            HtmlToken::StartTag { tag, self_closing, .. } if !self_closing && !is_void_element(&tag) => {
+                stack.push(tag);
+            }
+            HtmlToken::EndTag { tag } => {
+                if is_void_element(&tag) {
+                    continue;
+                }
+                match stack.iter().rposition(|t| *t == tag) {
+                    Some(index) => {
+                        // 対応する開始タグより後に開かれたタグは閉じられていない
+                        for unclosed in stack.drain(index + 1..).rev() {
+                            errors.push(format!("<{}> is not closed before </{}>", unclosed, tag));
+                        }
+                        stack.pop();
+                    }
+                    None => errors.push(format!("</{}> has no matching start tag", tag)),
+                }
+            }
+            _ => {}
+        }
+    }
+    for unclosed in stack.iter().rev() {
+        errors.push(format!("<{}> is not closed", unclosed));
+    }
+
+    if errors.is_empty() {
+        return Ok(());
+    }
+    return Err(errors);
+}
+
+// テキストとして出力する際に特殊文字(&, <, >)をエスケープする
+pub fn escape_text(s: &str) -> String {
+    let mut escaped = String::new();
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            _ => escaped.push(c),
+        }
+    }
+    return escaped;
+}
+
+// 属性値として出力する際に特殊文字(&, ")をエスケープする
+pub fn escape_attribute(s: &str) -> String {
+    let mut escaped = String::new();
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    return escaped;
+}
+
+// 許可リストにないタグで、中身も文字として残さずに取り除く要素
+const CONTENT_DROPPED_ELEMENTS: &[&str] = &["iframe", "noscript", "script", "style", "template", "textarea"];
+
+// 許可リストにないタグを取り除くトークン列のアダプタ
+// 取り除いたタグの中の文字はそのまま残すが、scriptやstyleなどCONTENT_DROPPED_ELEMENTSの要素は中身ごと取り除く
+// 許可したタグからもonclickなどのイベントハンドラ属性を取り除き、コメントも取り除く
+pub struct AllowedTags<'a, I: Iterator<Item = HtmlToken>> {
+    iter: I,
+    allowed: &'a [&'a str],
+    // 中身ごと取り除いている要素のタグ名と、その中で入れ子になっている同じタグの数
+    skipping: Option<(String, usize)>,
+}
+
+impl<I: Iterator<Item = HtmlToken>> Iterator for AllowedTags<'_, I> {
+    type Item = HtmlToken;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let token = self.iter.next()?;
+            // scriptやstyleなどの中身を読み飛ばしている場合、対応する終了タグまで取り除く
+            if let Some((skipping, depth)) = &mut self.skipping {
+                match token {
+                    HtmlToken::StartTag { ref tag, self_closing: false, .. } if tag.eq_ignore_ascii_case(skipping) => {
+                        *depth += 1;
+                    }
+                    HtmlToken::EndTag { ref tag } if tag.eq_ignore_ascii_case(skipping) => {
+                        if *depth == 0 {
+                            self.skipping = None;
+                        } else {
+                            *depth -= 1;
+                        }
+                    }
+                    // 入力の終わりは読み飛ばさずに返す
+                    HtmlToken::Eof => return Some(token),
+                    _ => {}
+                }
+                continue;
+            }
+            match token {
+                HtmlToken::StartTag { ref tag, self_closing, .. } if !self.is_allowed(tag) => {
+                    if !self_closing && CONTENT_DROPPED_ELEMENTS.iter().any(|e| e.eq_ignore_ascii_case(tag)) {
+                        self.skipping = Some((tag.clone(), 0));
+                    }
+                }
+                HtmlToken::StartTag { tag, self_closing, attributes } => {
+                    let attributes = attributes.into_iter().filter(|a| !is_event_handler_attribute(&a.name())).collect();
+                    return Some(HtmlToken::StartTag { tag, self_closing, attributes });
+                }
+                HtmlToken::EndTag { ref tag } if !self.is_allowed(tag) => {}
+                HtmlToken::Comment(_) => {}
+                _ => return Some(token),
+            }
+        }
+    }
+}
+
+impl<I: Iterator<Item = HtmlToken>> AllowedTags<'_, I> {
+    fn is_allowed(&self, tag: &str) -> bool {
+        return self.allowed.iter().any(|a| a.eq_ignore_ascii_case(tag));
+    }
+}
+
+// トークン列から許可リストにないタグ、イベントハンドラ属性、コメントを取り除く
+pub fn allowed_tags<'a, I: Iterator<Item = HtmlToken>>(iter: I, allowed: &'a [&'a str]) -> AllowedTags<'a, I> {
+    return AllowedTags { iter, allowed, skipping: None };
+}
+
+// 開始タグの指定した名前の属性(名前の大文字小文字は区別しない)の値を小文字にするトークン列のアダプタ
+// type属性のように値の大文字小文字を区別しない属性を正規化するために使い、他の属性とトークンはそのまま返す
+pub fn normalize_attribute_case<I: Iterator<Item = HtmlToken>>(iter: I, attr: &str) -> impl Iterator<Item = HtmlToken> {
+    let attr = attr.to_string();
+    return iter.map(move |token| match token {
+        HtmlToken::StartTag { tag, self_closing, attributes } => {
+            let attributes = attributes
+                .into_iter()
+                .map(|attribute| {
+                    if !attribute.name().eq_ignore_ascii_case(&attr) {
+                        return attribute;
+                    }
+                    let mut normalized = Attribute::new();
+                    attribute.name().chars().for_each(|c| normalized.add_char(c, true));
+                    attribute.value().to_ascii_lowercase().chars().for_each(|c| normalized.add_char(c, false));
+                    normalized
+                })
+                .collect();
+            HtmlToken::StartTag { tag, self_closing, attributes }
+        }
+        _ => token,
+    });
+}
+
+// トークン列から画面に表示される文字を取り出す
+// scriptとstyleの中身は取り除き、連続するASCIIの空白は1つの空白にまとめ、前後の空白は取り除く
+pub fn collect_text(tokenizer: HtmlTokenizer<'_>) -> String {
+    let mut text = String::new();
+    let mut skipping: Option<String> = None;
+    let mut pending_space = false;
+    for token in tokenizer {
+        match token {
+            HtmlToken::StartTag { tag, self_closing: false, .. } if skipping.is_none() && (tag == "script" || tag == "style") => {
+                skipping = Some(tag);
+            }
+            HtmlToken::EndTag { tag } if skipping.as_deref() == Some(tag.as_str()) => skipping = None,
+            HtmlToken::Char(c) if skipping.is_none() => {
+                if c.is_ascii_whitespace() {
+                    pending_space = true;
+                    continue;
+                }
+                // 文字列の先頭の空白は出力しない
+                if pending_space && !text.is_empty() {
+                    text.push(' ');
+                }
+                pending_space = false;
+                text.push(c);
+            }
+            _ => {}
+        }
+    }
+    return text;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    // テキストの特殊文字が全てエスケープされる場合
+    #[test]
+    fn test_escape_text() {
+        assert_eq!(escape_text("a & b < c > d \"e\""), "a &amp; b &lt; c &gt; d \"e\"");
+    }
+
+    // 属性値の特殊文字が全てエスケープされる場合
+    #[test]
+    fn test_escape_attribute() {
+        assert_eq!(escape_attribute("a & b \"c\" <d>"), "a &amp; b &quot;c&quot; <d>");
+    }
+
+    // エスケープ済みの文字列を再度エスケープすると二重にエスケープされる場合
+    #[test]
+    fn test_escape_twice() {
+        assert_eq!(escape_text(&escape_text("&")), "&amp;amp;");
+        assert_eq!(escape_attribute(&escape_attribute("&")), "&amp;amp;");
+    }
+
+    // 開始タグと終了タグの対応が取れている場合
+    #[test]
+    fn test_check_tag_balance_ok() {
+        let tokenizer = HtmlTokenizer::new("<a><b></b><br><img/></a>".to_string());
+        assert_eq!(check_tag_balance(tokenizer), Ok(()));
+    }
+
+    // 開始タグと終了タグが交差している場合
+    #[test]
+    fn test_check_tag_balance_mismatch() {
+        let tokenizer = HtmlTokenizer::new("<a><b></a></b>".to_string());
+        assert_eq!(
+            check_tag_balance(tokenizer),
+            Err(vec![
+                "<b> is not closed before </a>".to_string(),
+                "</b> has no matching start tag".to_string(),
+            ])
+        );
+    }
+
+    // 閉じられていないタグがある場合
+    #[test]
+    fn test_check_tag_balance_unclosed() {
+        let tokenizer = HtmlTokenizer::new("<p><span>".to_string());
+        assert_eq!(
+            check_tag_balance(tokenizer),
+            Err(vec!["<span> is not closed".to_string(), "<p> is not closed".to_string()])
+        );
+    }
+
+    // 文字のトークンが異なる場合
+    #[test]
+    fn test_diff_tokens() {
+        let expected = vec![TokenDiff {
+            index: 1,
+            expected: Some(HtmlToken::Char('a')),
+            actual: Some(HtmlToken::Char('b')),
+        }];
+        assert_eq!(diff_tokens("<p>a</p>", "<p>b</p>"), expected);
+    }
+
+    // 同じ場合と、一方の入力が途中で終わる場合
+    #[test]
+    fn test_diff_tokens_same_and_shorter() {
+        assert!(diff_tokens("<p>a</p>", "<P>a</p>").is_empty());
+        let expected = vec![TokenDiff { index: 2, expected: Some(HtmlToken::EndTag { tag: "p".to_string() }), actual: Some(HtmlToken::Eof) }];
+        assert_eq!(diff_tokens("<p>a</p>", "<p>a"), expected);
+    }
+
+    // 3つのh2タグの位置と属性を返す場合
+    #[test]
+    fn test_find_all_start_tags() {
+        let html = "<h1>t</h1><h2 id=\"a\">A</h2>\n<p>あ</p><H2 id=\"b\">B</H2><h2>C</h2>";
+        let found = find_all_start_tags(html, "h2");
+        let positions: Vec<usize> = found.iter().map(|(position, _)| *position).collect();
+        assert_eq!(positions, vec![10, 38, 55]);
+        for &position in &positions {
+            assert!(html[position..].to_ascii_lowercase().starts_with("<h2"));
+        }
+        assert_eq!(found[0].1[0].value(), "a".to_string());
+        assert_eq!(found[1].1[0].value(), "b".to_string());
+        assert!(found[2].1.is_empty());
+    }
+
+    // </head>で打ち切る場合
+    #[test]
+    fn test_head_tokens() {
+        let tokens = head_tokens("<head><title>t</title></head><body><p>x</p></body>");
+        let expected = vec![
+            HtmlToken::StartTag { tag: "head".to_string(), self_closing: false, attributes: Vec::new() },
+            HtmlToken::StartTag { tag: "title".to_string(), self_closing: false, attributes: Vec::new() },
+            HtmlToken::Char('t'),
+            HtmlToken::EndTag { tag: "title".to_string() },
+            HtmlToken::EndTag { tag: "head".to_string() },
+        ];
+        assert_eq!(tokens, expected);
+    }
+
+    // </head>がなく、<body>で打ち切る場合
+    #[test]
+    fn test_head_tokens_without_head_end_tag() {
+        let tokens = head_tokens("<meta charset=utf-8><BODY><p>x</p>");
+        assert_eq!(tokens.len(), 1);
+        assert!(matches!(&tokens[0], HtmlToken::StartTag { tag, .. } if tag == "meta"));
+    }
+
+    // 連続する空白を1つにまとめ、前後の空白を取り除く場合
+    #[test]
+    fn test_collect_text_whitespace() {
+        assert_eq!(collect_text(HtmlTokenizer::new("<p>Hello   <b>world</b></p>".to_string())), "Hello world");
+        assert_eq!(collect_text(HtmlTokenizer::new(" \n<p> a\t\n b </p>\n ".to_string())), "a b");
+        assert_eq!(collect_text(HtmlTokenizer::new("<p>  </p>".to_string())), "");
+    }
+
+    // scriptとstyleの中身を取り除く場合
+    #[test]
+    fn test_collect_text_skips_script_and_style() {
+        let html = "<p>a</p><script>var s = \"<p>x</p>\";</script><style>p { color: red; }</style><p>b</p>";
+        assert_eq!(collect_text(HtmlTokenizer::new(html.to_string())), "ab");
+    }
+
+    // <input> のtype属性の値だけを小文字にする場合
+    #[test]
+    fn test_normalize_attribute_case() {
+        let tokenizer = HtmlTokenizer::new("<input TYPE=\"TEXT\" name=\"Q\"><input type=\"Checkbox\"><p type=\"A\">X</p>".to_string());
+        let tags: Vec<String> = normalize_attribute_case(tokenizer, "type").map(|token| token.reserialize()).collect();
+        assert_eq!(
+            tags.join(""),
+            "<input type=\"text\" name=\"Q\"><input type=\"checkbox\"><p type=\"a\">X</p>".to_string()
+        );
+    }
+
+    // 許可リストにないタグを取り除く場合
+    #[test]
+    fn test_allowed_tags() {
+        let tokenizer = HtmlTokenizer::new("<div><p>a<b>b</b></p><script>js</script><style>p{}</style></div>".to_string());
+        let tokens: Vec<HtmlToken> = allowed_tags(tokenizer, &["p"]).collect();
+        let expected = vec![
+            HtmlToken::StartTag { tag: "p".to_string(), self_closing: false, attributes: Vec::new() },
+            HtmlToken::Char('a'),
+            HtmlToken::Char('b'),
+            HtmlToken::EndTag { tag: "p".to_string() },
+            HtmlToken::Eof,
+        ];
+        assert_eq!(tokens, expected);
+    }
+
+    // 許可したタグのイベントハンドラ属性とコメントを取り除く場合
+    #[test]
+    fn test_allowed_tags_drops_event_handlers_and_comments() {
+        let tokenizer = HtmlTokenizer::new("<p onclick=\"x()\" title=t>a<!-- c --></p>".to_string());
+        let tokens: Vec<HtmlToken> = allowed_tags(tokenizer, &["p"]).collect();
+        match &tokens[0] {
+            HtmlToken::StartTag { tag, attributes, .. } => {
+                assert_eq!(tag, "p");
+                let names: Vec<String> = attributes.iter().map(|a| a.name()).collect();
+                assert_eq!(names, vec!["title".to_string()]);
+            }
+            t => panic!("unexpected token: {:?}", t),
+        }
+        let expected = vec![HtmlToken::Char('a'), HtmlToken::EndTag { tag: "p".to_string() }, HtmlToken::Eof];
+        assert_eq!(tokens[1..], expected);
+    }
+
+    // 許可リストにないiframe、template、noscript、textareaを中身ごと取り除く場合
+    #[test]
+    fn test_allowed_tags_drops_content_of_embedded_elements() {
+        let html = "<p>a</p><iframe>i</iframe><template><template>t</template>u</template><noscript>n</noscript><textarea>x</textarea>b";
+        let tokens: Vec<HtmlToken> = allowed_tags(HtmlTokenizer::new(html.to_string()), &["p"]).collect();
+        let text: String = tokens
+            .iter()
+            .filter_map(|t| match t {
+                HtmlToken::Char(c) => Some(*c),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(text, "ab".to_string());
+        assert_eq!(tokens.last(), Some(&HtmlToken::Eof));
+    }
+
+    // 開始タグが含まれているかを判定する場合
+    #[test]
+    fn test_contains_tag() {
+        assert!(contains_tag("<p><form>", "form"));
+        assert!(contains_tag("<p><FORM action=x>", "Form"));
+        assert!(!contains_tag("<p>", "form"));
+        assert!(!contains_tag("<p>form</form>", "form"));
+    }
+}
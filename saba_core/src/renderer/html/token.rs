@@ -1,6 +1,76 @@
-use alloc::string::String;
+use alloc::borrow::Cow;
+use alloc::collections::BTreeSet;
+use alloc::format;
+use alloc::string::{String, ToString};
 use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
 use crate::renderer::html::attribute::Attribute;
+use crate::renderer::html::escape_attribute;
+
+// 名前付き文字参照(&amp; など)と、それが表す文字
+const NAMED_CHARACTER_REFERENCES: &[(&str, char)] = &[
+    ("amp", '&'),
+    ("lt", '<'),
+    ("gt", '>'),
+    ("quot", '"'),
+    ("apos", '\''),
+    ("nbsp", '\u{A0}'),
+];
+
+// & の直後から始まる文字参照を解釈し、表す文字と & の後に読んだバイト数を返す
+// 数値文字参照(&#123; &#x1F4A9;)の ; は省略でき、名前付き文字参照は ; が必要
+fn parse_character_reference(input: &str) -> Option<(char, usize)> {
+    let bytes = input.as_bytes();
+    if bytes.first() == Some(&b'#') {
+        let (radix, digits_start) = match bytes.get(1) {
+            Some(b'x') | Some(b'X') => (16, 2),
+            _ => (10, 1),
+        };
+        let mut i = digits_start;
+        let mut value: u32 = 0;
+        while let Some(digit) = bytes.get(i).and_then(|b| (*b as char).to_digit(radix)) {
+            value = value.saturating_mul(radix).saturating_add(digit);
+            i += 1;
+        }
+        // 数字が1つもない場合は文字参照ではない
+        if i == digits_start {
+            return None;
+        }
+        if bytes.get(i) == Some(&b';') {
+            i += 1;
+        }
+        // NULや範囲外、サロゲートの場合はU+FFFDに置き換える
+        let c = match value {
+            0 => '\u{FFFD}',
+            _ => char::from_u32(value).unwrap_or('\u{FFFD}'),
+        };
+        return Some((c, i));
+    }
+    for (name, c) in NAMED_CHARACTER_REFERENCES {
+        let len = name.len();
+        if bytes.len() > len && &bytes[..len] == name.as_bytes() && bytes[len] == b';' {
+            return Some((*c, len + 1));
+        }
+    }
+    return None;
+}
+
+// タグの中で区切りとして扱う空白(タブ、改行、改ページ、復帰、スペース)かどうかを判定する
+fn is_html_whitespace(c: char) -> bool {
+    return matches!(c, '\t' | '\n' | '\x0C' | '\r' | ' ');
+}
+
+// 要素名から、その中身を文字として読む状態を返す(中身を文字として扱わない要素はNone)
+// RCDATA要素(title, textarea)では文字参照をデコードし、RAWTEXT要素(style, iframeなど)とscriptではデコードしない
+fn raw_text_state(tag: &str) -> Option<State> {
+    match tag {
+        "script" => return Some(State::ScriptData),
+        "title" | "textarea" => return Some(State::Rcdata),
+        "style" | "iframe" | "xmp" | "noembed" | "noframes" => return Some(State::RawText),
+        _ => return None,
+    }
+}
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 // トークンの列挙型
@@ -15,18 +85,109 @@ pub enum HtmlToken {
     EndTag {
         tag: String,
     },
+    // DOCTYPE宣言(名前は小文字に変換する)
+    Doctype {
+        name: Option<String>,
+    },
+    // コメント(<!-- と --> の間の文字列)
+    Comment(String),
     // 文字
     Char(char),
     //ファイルの終了
     Eof,
 }
 
+impl HtmlToken {
+    // 属性やself_closingを無視して、同じ種類(開始タグ同士か終了タグ同士)かつ同じタグ名かを判定する
+    pub fn same_tag_kind(&self, other: &HtmlToken) -> bool {
+        match (self, other) {
+            (HtmlToken::StartTag { tag: a, .. }, HtmlToken::StartTag { tag: b, .. })
+            | (HtmlToken::EndTag { tag: a }, HtmlToken::EndTag { tag: b })
+                => return a == b,
+            _ => return false,
+        }
+    }
+
+    // 開始タグを元のソースに近い形の文字列に戻す
+    // 属性値は " で囲み(値の中の " と & はエスケープする)、値が空の属性は disabled のように名前のみを書き出す
+    // 開始タグ以外のトークンはDisplayと同じ文字列を返す
+    pub fn reserialize(&self) -> String {
+        let (tag, self_closing, attributes) = match self {
+            HtmlToken::StartTag { tag, self_closing, attributes } => (tag, self_closing, attributes),
+            _ => return self.to_string(),
+        };
+        let mut s = format!("<{}", tag);
+        for attribute in attributes {
+            if attribute.value().is_empty() {
+                s.push_str(&format!(" {}", attribute.name()));
+            } else {
+                s.push_str(&format!(" {}=\"{}\"", attribute.name(), escape_attribute(&attribute.value())));
+            }
+        }
+        if *self_closing {
+            s.push_str(" /");
+        }
+        s.push('>');
+        return s;
+    }
+}
+
+// 入力中の位置(行と列はどちらも1から数える)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+// 開始タグの属性の名前と値が入力中のどこにあるか(バイト単位の範囲)
+// 値の範囲は引用符を含まず、値を持たない属性の場合はNoneになる
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttributeSpan {
+    pub name: Range<usize>,
+    pub value: Option<Range<usize>>,
+}
+
+impl fmt::Display for HtmlToken {
+    // トークンをHTMLの文字列に戻す(属性値は " で囲み、Charはエスケープせずにそのまま書き出す)
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HtmlToken::StartTag { tag, self_closing, attributes } => {
+                write!(f, "<{}", tag)?;
+                for attribute in attributes {
+                    write!(f, " {}=\"{}\"", attribute.name(), escape_attribute(&attribute.value()))?;
+                }
+                if *self_closing {
+                    write!(f, " /")?;
+                }
+                return write!(f, ">");
+            }
+            HtmlToken::EndTag { tag } => return write!(f, "</{}>", tag),
+            HtmlToken::Doctype { name: Some(name) } => return write!(f, "<!DOCTYPE {}>", name),
+            HtmlToken::Doctype { name: None } => return write!(f, "<!DOCTYPE>"),
+            HtmlToken::Comment(comment) => return write!(f, "<!--{}-->", comment),
+            HtmlToken::Char(c) => return write!(f, "{}", c),
+            HtmlToken::Eof => return Ok(()),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 // Tokenizerがとる状態の列挙型
 pub enum State {
     Data,
     TagOpen,
     EndTagOpen,
+    MarkupDeclarationOpen,
+    BogusComment,
+    CommentStart,
+    CommentStartDash,
+    Comment,
+    CommentEndDash,
+    CommentEnd,
+    Doctype,
+    BeforeDoctypeName,
+    DoctypeName,
+    AfterDoctypeName,
     TagName,
     BeforeAttributeName,
     AttributeName,
@@ -37,6 +198,10 @@ pub enum State {
     AttributeValueUnquoted,
     AfterAttributeValueQuoted,
     SelfClosingStartTag,
+    CharacterReference,
+    Rcdata,
+    RawText,
+    // 以下の終了タグを読む状態はRCDATAとRAWTEXTでも共有する
     ScriptData,
     ScriptDataLessThanSign,
     ScriptDataEndTagOpen,
@@ -44,44 +209,103 @@ pub enum State {
     TemporaryBuffer,
 }
 
+// 入力はVec<char>に展開せずに文字列のまま持ち、posはバイト単位の位置を表す
+// (from_strで作成した場合は、入力の文字列を借用してコピーしない)
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct HtmlTokenizer {
+pub struct HtmlTokenizer<'a> {
     state: State,
     pos: usize,
+    prev_pos: usize,
     reconsume: bool,
     latest_token: Option<HtmlToken>,
-    input: Vec<char>,
+    input: Cow<'a, str>,
     buf: String,
+    max_attributes: usize,
+    max_tokens: Option<usize>,
+    token_count: usize,
+    truncated: bool,
+    pending_token: Option<HtmlToken>,
+    raw_text_element: Option<String>,
+    return_state: State,
+    attribute_spans: Vec<AttributeSpan>,
+    last_attribute_spans: Vec<AttributeSpan>,
+    max_attributes_per_tag: Option<usize>,
+    dropping_attributes: bool,
+    // 作成中のタグで、名前を読み終えた属性の名前
+    attribute_names: BTreeSet<String>,
+    parse_errors: Vec<String>,
+    start_tag_offset: usize,
+    last_start_tag_offset: usize,
+    decode_entities: bool,
+    // 状態遷移のループを回った回数(入力の長さに比例することをテストで確かめる)
+    steps: usize,
 }
 
-impl HtmlTokenizer {
+impl<'a> HtmlTokenizer<'a> {
     pub fn new(html: String) -> Self {
+        return Self::with_input(Cow::Owned(html));
+    }
+    // 入力の文字列をコピーせずに借用してトークナイザを作成する
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(html: &'a str) -> Self {
+        return Self::with_input(Cow::Borrowed(html));
+    }
+    fn with_input(input: Cow<'a, str>) -> Self {
         Self {
             state: State::Data,
             pos: 0,
+            prev_pos: 0,
             reconsume: false,
             latest_token: None,
-            input: html.chars().collect(),
+            input,
             buf: String::new(),
+            max_attributes: 0,
+            max_tokens: None,
+            token_count: 0,
+            truncated: false,
+            pending_token: None,
+            raw_text_element: None,
+            return_state: State::Data,
+            attribute_spans: Vec::new(),
+            last_attribute_spans: Vec::new(),
+            max_attributes_per_tag: None,
+            dropping_attributes: false,
+            attribute_names: BTreeSet::new(),
+            parse_errors: Vec::new(),
+            start_tag_offset: 0,
+            last_start_tag_offset: 0,
+            decode_entities: true,
+            steps: 0,
         }
     }
     // Eof判定
     fn is_eof(&self) -> bool {
         return self.pos > self.input.len();
     }
-    // 現posの文字を読み取り、posを1進める
+    // 現posの文字を読み取り、posを次の文字まで進める
+    // 入力の終わりを超えた場合は '\0' を返し、posを1進める(EOFかどうかはis_eofで判定する)
     fn consume_next_input(&mut self) -> char {
-        let c = self.input[self.pos];
-        self.pos += 1;
-        return c;
+        self.prev_pos = self.pos;
+        match self.input.get(self.pos..).and_then(|rest| rest.chars().next()) {
+            Some(c) => {
+                self.pos += c.len_utf8();
+                return c;
+            }
+            None => {
+                self.pos += 1;
+                return '\0';
+            }
+        }
     }
-    // 現posの1つ前の文字を読み取る
+    // 最後に読んだ文字をもう一度読み取る
     fn reconsume_input(&mut self) -> char {
         self.reconsume = false;
-        return self.input[self.pos - 1];
+        return self.input.get(self.prev_pos..).and_then(|rest| rest.chars().next()).unwrap_or('\0');
     }
     // StartTagもしくはEndTagトークンを作成し、latest_tokenにセットする
     fn create_tag(&mut self, start_tag_token: bool) {
+        self.dropping_attributes = false;
+        self.attribute_names.clear();
         if start_tag_token {
             self.latest_token = Some(HtmlToken::StartTag { 
                 tag: String::new(),
@@ -104,32 +328,123 @@ impl HtmlTokenizer {
             }
         }
     }
+    // 現在の文字から始まる入力が指定した文字列(大文字小文字は区別しない)と一致する場合、その文字列の末尾まで読み進める
+    fn consume_if_matches(&mut self, word: &str) -> bool {
+        let start = self.prev_pos;
+        let matches = match self.input.get(start..start + word.len()) {
+            Some(s) => s.eq_ignore_ascii_case(word),
+            None => false,
+        };
+        if matches {
+            self.pos = start + word.len();
+        }
+        return matches;
+    }
+    // Commentトークンの末尾に文字を追加する
+    fn append_comment(&mut self, c: char) {
+        assert!(self.latest_token.is_some());
+        if let Some(HtmlToken::Comment(ref mut comment)) = self.latest_token {
+            comment.push(c);
+        }
+    }
+    // 作成中のトークンを返して入力の終わりをもう一度処理する(次の呼び出しでEofトークンを返す)
+    fn take_latest_token_at_eof(&mut self) -> Option<HtmlToken> {
+        self.reconsume = true;
+        self.state = State::Data;
+        return self.take_latest_token();
+    }
+    // Doctypeトークンの名前の末尾に文字を追加する
+    fn append_doctype_name(&mut self, c: char) {
+        assert!(self.latest_token.is_some());
+        if let Some(HtmlToken::Doctype { ref mut name }) = self.latest_token {
+            name.get_or_insert_with(String::new).push(c);
+        }
+    }
     // 最新のlatest_tokenを返し、リセットする
     fn take_latest_token(&mut self) -> Option<HtmlToken> {
         assert!(self.latest_token.is_some());
 
-        let t = self.latest_token.as_ref().cloned();
-        self.latest_token = None;
+        // 属性の多いタグでもコピーしないように、cloneせずに取り出す
+        let mut t = self.latest_token.take();
         assert!(self.latest_token.is_none());
+
+        // 同じ名前の属性が複数ある場合は、最初の属性のみを残す(名前は小文字に変換済み)
+        // 属性の位置も同じように取り除き、属性と同じ順に並べる
+        let mut spans = core::mem::take(&mut self.attribute_spans);
+        if let Some(HtmlToken::StartTag { ref mut attributes, .. }) = t {
+            let mut seen = BTreeSet::new();
+            let keep: Vec<bool> = attributes.iter().map(|attribute| seen.insert(attribute.name())).collect();
+            let mut keep_iter = keep.iter();
+            attributes.retain(|_| *keep_iter.next().unwrap_or(&true));
+            let mut keep_iter = keep.iter();
+            spans.retain(|_| *keep_iter.next().unwrap_or(&true));
+            self.last_attribute_spans = spans;
+            self.last_start_tag_offset = self.start_tag_offset;
+        }
+
+        // script、RCDATA要素、RAWTEXT要素の開始タグの場合、終了タグまでの中身を文字として扱う状態に遷移
+        if let Some(HtmlToken::StartTag { ref tag, self_closing: false, .. }) = t {
+            if let Some(state) = raw_text_state(tag) {
+                self.state = state;
+                self.raw_text_element = Some(tag.clone());
+            }
+        }
         return t;
     }
+    // 中身を文字として扱う要素の中で、文字を読む状態を返す
+    fn current_raw_text_state(&self) -> State {
+        return self.raw_text_element.as_deref().and_then(raw_text_state).unwrap_or(State::Data);
+    }
     // latest_tokenにAttributeを追加する
     fn start_new_attribute(&mut self) {
         assert!(self.latest_token.is_some());
 
         if let Some(t) = self.latest_token.as_mut() {
             match t {
-                HtmlToken::StartTag { tag: _, self_closing: _, ref mut attributes} 
+                HtmlToken::StartTag { ref tag, self_closing: _, ref mut attributes} 
                     => {
+                        // 直前の属性が同じ名前の属性の重複であれば取り除き、上限には最初の属性のみを数える
+                        // (読み捨てている間は直前の属性は確認済み)
+                        if let Some(last) = attributes.last().filter(|_| !self.dropping_attributes) {
+                            if !self.attribute_names.insert(last.name()) {
+                                attributes.pop();
+                                self.attribute_spans.pop();
+                            }
+                        }
+                        // 属性数の上限に達している場合、このタグの以降の属性は読み捨て、parse errorを1度だけ記録する
+                        if let Some(limit) = self.max_attributes_per_tag {
+                            if attributes.len() >= limit {
+                                if !self.dropping_attributes {
+                                    self.parse_errors.push(format!("<{}> has more than {} attributes", tag, limit));
+                                }
+                                self.dropping_attributes = true;
+                                return;
+                            }
+                        }
                         attributes.push(Attribute::new());
+                        self.attribute_spans.push(AttributeSpan { name: self.prev_pos..self.prev_pos, value: None });
+                        // 1つのタグが持つ属性数の最大値を更新する
+                        if attributes.len() > self.max_attributes {
+                            self.max_attributes = attributes.len();
+                        }
                     }
-                _ => panic!("`latest_token` should be either StartTag"),
+                // 終了タグは属性を持てないため、属性は読み捨てる
+                HtmlToken::EndTag { tag: _ } => {}
+                _ => panic!("`latest_token` should be either StartTag or EndTag"),
             }
         }
     }
     // latest_tokenに属性文字を追加する
     fn append_attribute(&mut self, c: char, is_name: bool) {
+        self.append_attribute_at(c, is_name, self.prev_pos..self.pos);
+    }
+    // latest_tokenに、入力中のrangeの範囲から読んだ属性文字を追加する
+    fn append_attribute_at(&mut self, c: char, is_name: bool, range: Range<usize>) {
         assert!(self.latest_token.is_some());
+        // 上限を超えて読み捨てている属性の文字は追加しない
+        if self.dropping_attributes {
+            return;
+        }
 
         if let Some(t) = self.latest_token.as_mut() {
             match t {
@@ -138,12 +453,27 @@ impl HtmlTokenizer {
                         let len = attributes.len();
                         assert!(len > 0);
                         attributes[len-1].add_char(c, is_name);
+                        // 属性の名前か値の範囲を、読んだ文字の終わりまで広げる
+                        if let Some(span) = self.attribute_spans.last_mut() {
+                            if is_name {
+                                if span.name.is_empty() {
+                                    span.name.start = range.start;
+                                }
+                                span.name.end = range.end;
+                            } else {
+                                let start = span.value.as_ref().map_or(range.start, |value| value.start);
+                                span.value = Some(start..range.end);
+                            }
+                        }
                     }
-                _ => panic!("`latest_token` should be either StartTag"),
+                // 終了タグは属性を持てないため、属性文字は読み捨てる
+                HtmlToken::EndTag { tag: _ } => {}
+                _ => panic!("`latest_token` should be either StartTag or EndTag"),
             }
         }
     }
     // latest_tokenが開始タグの場合、self_closingフラグをtrueにする
+    // 終了タグの / は仕様上parse errorとして無視されるため、何もしない
     fn set_self_closing_flag(&mut self) {
         assert!(self.latest_token.is_some());
 
@@ -151,21 +481,132 @@ impl HtmlTokenizer {
             match t {
                 HtmlToken::StartTag { tag: _, ref mut self_closing, attributes: _ }
                     => *self_closing = true,
-                _ => panic!("`latest_token` should be either StartTag"),
+                HtmlToken::EndTag { tag: _ } => {}
+                _ => panic!("`latest_token` should be either StartTag or EndTag"),
             }
         }
     }
+    // 生成するトークン数の上限を設定する
+    pub fn set_max_tokens(&mut self, max_tokens: usize) {
+        self.max_tokens = Some(max_tokens);
+    }
+    // 1つの開始タグが持てる属性数の上限を設定する
+    // 上限を超えた属性は読み捨て、parse errorとして記録する(タグ自体は出力する)
+    pub fn set_max_attributes_per_tag(&mut self, max_attributes: usize) {
+        self.max_attributes_per_tag = Some(max_attributes);
+    }
+    // 文字参照をデコードするかどうかを設定する(デフォルトはtrue)
+    // falseの場合、&amp; などの文字参照を元の文字のまま出力する
+    pub fn set_decode_entities(&mut self, decode_entities: bool) {
+        self.decode_entities = decode_entities;
+    }
+    // 記録したparse errorの一覧を返す
+    pub fn parse_errors(&self) -> Vec<String> {
+        return self.parse_errors.clone();
+    }
+    // 入力はそのままに、最初から読み直せるように状態をリセットする(set_max_tokens、set_max_attributes_per_tag、set_decode_entitiesの設定は残す)
+    pub fn rewind(&mut self) {
+        self.state = State::Data;
+        self.pos = 0;
+        self.prev_pos = 0;
+        self.reconsume = false;
+        self.latest_token = None;
+        self.buf = String::new();
+        self.max_attributes = 0;
+        self.token_count = 0;
+        self.truncated = false;
+        self.pending_token = None;
+        self.raw_text_element = None;
+        self.return_state = State::Data;
+        self.attribute_spans = Vec::new();
+        self.last_attribute_spans = Vec::new();
+        self.dropping_attributes = false;
+        self.attribute_names.clear();
+        self.parse_errors = Vec::new();
+        self.start_tag_offset = 0;
+        self.last_start_tag_offset = 0;
+        self.steps = 0;
+    }
+    // トークン数の上限に達し、トークンの生成を打ち切ったかどうかを返す
+    pub fn is_truncated(&self) -> bool {
+        return self.truncated;
+    }
+    // 連続するCharトークンを読み進めて文字列として返す
+    // Char以外のトークンは読み戻し、次のnextで返されるようにする
+    pub fn collect_text(&mut self) -> String {
+        let mut text = String::new();
+        loop {
+            match self.next() {
+                Some(HtmlToken::Char(c)) => text.push(c),
+                Some(t) => {
+                    self.pending_token = Some(t);
+                    break;
+                }
+                None => break,
+            }
+        }
+        return text;
+    }
+    // 指定した名前(大文字小文字は区別しない)の開始タグまでトークンを読み飛ばし、その開始タグを返す
+    pub fn skip_to_start_tag(&mut self, name: &str) -> Option<HtmlToken> {
+        for token in self.by_ref() {
+            if let HtmlToken::StartTag { ref tag, .. } = token {
+                if tag.eq_ignore_ascii_case(name) {
+                    return Some(token);
+                }
+            }
+        }
+        return None;
+    }
+    // scriptなど、中身を文字として扱う要素の中にいる場合、その要素名を返す
+    pub fn raw_text_context(&self) -> Option<&str> {
+        return self.raw_text_element.as_deref();
+    }
+    // 最後に返したトークンの終わりの位置(トークンの直後の、次に読む文字の行と列)を返す
+    // 行と列はどちらも1から数え、改行の次の文字から列を1に戻す
+    pub fn current_position(&self) -> Position {
+        let end = self.pos.min(self.input.len());
+        let mut position = Position { line: 1, column: 1 };
+        for c in self.input[..end].chars() {
+            if c == '\n' {
+                position.line += 1;
+                position.column = 1;
+            } else {
+                position.column += 1;
+            }
+        }
+        return position;
+    }
+    // 次のトークンと、それが開始タグの場合は各属性の名前と値の位置を返す(属性と同じ順に並ぶ)
+    pub fn next_with_attr_spans(&mut self) -> Option<(HtmlToken, Vec<AttributeSpan>)> {
+        let token = self.next()?;
+        let spans = match token {
+            HtmlToken::StartTag { .. } => self.last_attribute_spans.clone(),
+            _ => Vec::new(),
+        };
+        return Some((token, spans));
+    }
+    // 最後に返した開始タグの、入力中の位置(< のバイト単位の位置)を返す
+    pub fn last_start_tag_offset(&self) -> usize {
+        return self.last_start_tag_offset;
+    }
+    // これまでに読んだタグのうち、1つのタグが持つ属性数の最大値を返す
+    pub fn max_attributes_on_tag(&self) -> usize {
+        return self.max_attributes;
+    }
 }
 
-impl Iterator for HtmlTokenizer {
-    type Item = HtmlToken;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.pos >= self.input.len() {
+impl HtmlTokenizer<'_> {
+    // 状態遷移を行いながら次のトークンを生成する
+    fn next_token(&mut self) -> Option<HtmlToken> {
+        // 入力が空の場合や、Eofトークンを返し終えた場合はNoneを返す
+        // (EOFの手前で作成中のタグを返した場合はreconsumeが立っているので、もう一度EOFを処理する)
+        if self.input.is_empty() || (self.is_eof() && !self.reconsume) {
             return None;
         }
 
         loop {
+            self.steps += 1;
             let c = match self.reconsume {
                 true => self.reconsume_input(),
                 false => self.consume_next_input(),
@@ -178,10 +619,20 @@ impl Iterator for HtmlTokenizer {
                         self.state = State::TagOpen;
                         continue;
                     }
+                    // & 記号の場合、CharacterReference状態に遷移
+                    if c == '&' {
+                        self.return_state = State::Data;
+                        self.state = State::CharacterReference;
+                        continue;
+                    }
                     // 最後の文字の場合、Eofトークンを返す
                     if self.is_eof() {
                         return Some(HtmlToken::Eof);
                     }
+                    // NULはU+FFFDに置き換える
+                    if c == '\0' {
+                        return Some(HtmlToken::Char('\u{FFFD}'));
+                    }
                     // それ以外の場合、文字トークンを返す 
                     return Some(HtmlToken::Char(c));
                 }
@@ -191,11 +642,18 @@ impl Iterator for HtmlTokenizer {
                         self.state = State::EndTagOpen;
                         continue;
                     }
+                    // ! 記号の場合、MarkupDeclarationOpen状態に遷移
+                    if c == '!' {
+                        self.state = State::MarkupDeclarationOpen;
+                        continue;
+                    }
                     // 文字がアルファベットで場合、TagName状態に遷移
                     if c.is_ascii_alphabetic() {
                         self.reconsume = true;
                         self.state = State::TagName;
                         self.create_tag(true);
+                        // 直前の < の位置を開始タグの位置として記録する
+                        self.start_tag_offset = self.prev_pos - 1;
                         continue;
                     }
                     // 最後の文字の場合、Eofトークンを返す
@@ -206,6 +664,161 @@ impl Iterator for HtmlTokenizer {
                     self.reconsume = true;
                     self.state = State::Data;
                 }
+                State::MarkupDeclarationOpen => {
+                    // -- の場合、CommentStart状態に遷移
+                    if self.consume_if_matches("--") {
+                        self.latest_token = Some(HtmlToken::Comment(String::new()));
+                        self.state = State::CommentStart;
+                        continue;
+                    }
+                    // DOCTYPE(大文字小文字は区別しない)の場合、Doctype状態に遷移
+                    if self.consume_if_matches("DOCTYPE") {
+                        self.latest_token = Some(HtmlToken::Doctype { name: None });
+                        self.state = State::Doctype;
+                        continue;
+                    }
+                    // それ以外の場合(parse error)、> までをコメントとして扱う
+                    self.latest_token = Some(HtmlToken::Comment(String::new()));
+                    self.reconsume = true;
+                    self.state = State::BogusComment;
+                }
+                State::BogusComment => {
+                    if c == '>' {
+                        self.state = State::Data;
+                        return self.take_latest_token();
+                    }
+                    if self.is_eof() {
+                        return self.take_latest_token_at_eof();
+                    }
+                    self.append_comment(c);
+                }
+                State::CommentStart => {
+                    if c == '-' {
+                        self.state = State::CommentStartDash;
+                        continue;
+                    }
+                    // <!--> の場合は空のコメントとして扱う
+                    if c == '>' {
+                        self.state = State::Data;
+                        return self.take_latest_token();
+                    }
+                    self.reconsume = true;
+                    self.state = State::Comment;
+                }
+                State::CommentStartDash => {
+                    if c == '-' {
+                        self.state = State::CommentEnd;
+                        continue;
+                    }
+                    // <!---> の場合は空のコメントとして扱う
+                    if c == '>' {
+                        self.state = State::Data;
+                        return self.take_latest_token();
+                    }
+                    if self.is_eof() {
+                        return self.take_latest_token_at_eof();
+                    }
+                    self.append_comment('-');
+                    self.reconsume = true;
+                    self.state = State::Comment;
+                }
+                State::Comment => {
+                    // - 記号の場合、閉じる --> の始まりかもしれないためCommentEndDash状態に遷移
+                    if c == '-' {
+                        self.state = State::CommentEndDash;
+                        continue;
+                    }
+                    // 最後の文字の場合、それまでの中身をCommentトークンとして返す
+                    if self.is_eof() {
+                        return self.take_latest_token_at_eof();
+                    }
+                    self.append_comment(c);
+                }
+                State::CommentEndDash => {
+                    if c == '-' {
+                        self.state = State::CommentEnd;
+                        continue;
+                    }
+                    if self.is_eof() {
+                        return self.take_latest_token_at_eof();
+                    }
+                    // --> ではなかったため、読んだ - をコメントに追加する
+                    self.append_comment('-');
+                    self.reconsume = true;
+                    self.state = State::Comment;
+                }
+                State::CommentEnd => {
+                    // --> の場合、Commentトークンを返す
+                    if c == '>' {
+                        self.state = State::Data;
+                        return self.take_latest_token();
+                    }
+                    // ---> のように - が続く場合、余分な - をコメントに追加する
+                    if c == '-' {
+                        self.append_comment('-');
+                        continue;
+                    }
+                    if self.is_eof() {
+                        return self.take_latest_token_at_eof();
+                    }
+                    self.append_comment('-');
+                    self.append_comment('-');
+                    self.reconsume = true;
+                    self.state = State::Comment;
+                }
+                State::Doctype => {
+                    // 空白の場合、BeforeDoctypeName状態に遷移
+                    if c.is_ascii_whitespace() {
+                        self.state = State::BeforeDoctypeName;
+                        continue;
+                    }
+                    // それ以外の場合、BeforeDoctypeName状態でもう一度判定する
+                    self.reconsume = true;
+                    self.state = State::BeforeDoctypeName;
+                }
+                State::BeforeDoctypeName => {
+                    // 空白は無視する
+                    if c.is_ascii_whitespace() {
+                        continue;
+                    }
+                    // > 記号の場合、名前のないDoctypeトークンを返す
+                    if c == '>' {
+                        self.state = State::Data;
+                        return self.take_latest_token();
+                    }
+                    // 最後の文字の場合、作成中のDoctypeトークンを返し、次の呼び出しでEofトークンを返す
+                    if self.is_eof() {
+                        return self.take_latest_token_at_eof();
+                    }
+                    // それ以外の場合、小文字に変換して名前に追加し、DoctypeName状態に遷移
+                    self.append_doctype_name(c.to_ascii_lowercase());
+                    self.state = State::DoctypeName;
+                }
+                State::DoctypeName => {
+                    // 空白の場合、AfterDoctypeName状態に遷移
+                    if c.is_ascii_whitespace() {
+                        self.state = State::AfterDoctypeName;
+                        continue;
+                    }
+                    if c == '>' {
+                        self.state = State::Data;
+                        return self.take_latest_token();
+                    }
+                    if self.is_eof() {
+                        return self.take_latest_token_at_eof();
+                    }
+                    self.append_doctype_name(c.to_ascii_lowercase());
+                }
+                State::AfterDoctypeName => {
+                    // PUBLICやSYSTEMなどの識別子は読み捨て、> 記号でDoctypeトークンを返す
+                    if c == '>' {
+                        self.state = State::Data;
+                        return self.take_latest_token();
+                    }
+                    if self.is_eof() {
+                        return self.take_latest_token_at_eof();
+                    }
+                }
                 State::EndTagOpen => {
                     // 最後の文字の場合、Eofトークンを返す
                     if self.is_eof() {
@@ -220,8 +833,8 @@ impl Iterator for HtmlTokenizer {
                     }
                 }
                 State::TagName => {
-                    // スペースの場合、BeforeAttributeName状態に遷移
-                    if c == ' ' {
+                    // 空白の場合、BeforeAttributeName状態に遷移
+                    if is_html_whitespace(c) {
                         self.state = State::BeforeAttributeName;
                         continue;
                     }
@@ -248,12 +861,23 @@ impl Iterator for HtmlTokenizer {
                     self.append_tag_name(c);
                 }
                 State::BeforeAttributeName => {
+                    // 空白は無視する
+                    if is_html_whitespace(c) {
+                        continue;
+                    }
                     // AfterAttributeName状態に遷移
                     if c == '/' || c == '>' || self.is_eof() {
                         self.reconsume = true;
                         self.state = State::AfterAttributeName;
                         continue;
                     }
+                    // = 記号の場合(parse error)、= を名前の先頭とする属性を始めてAttributeName状態に遷移
+                    if c == '=' {
+                        self.start_new_attribute();
+                        self.append_attribute(c, true);
+                        self.state = State::AttributeName;
+                        continue;
+                    }
                     // それ以外の場合、AfterAttributeName状態に遷移し、start_new_attributeメソッドを呼びだす
                     self.reconsume = true;
                     self.state = State::AttributeName;
@@ -261,7 +885,7 @@ impl Iterator for HtmlTokenizer {
                 }
                 State::AttributeName => {
                     // AfterAttributeName状態に遷移
-                    if c == ' ' || c == '/' || c == '>' || self.is_eof() {
+                    if is_html_whitespace(c) || c == '/' || c == '>' || self.is_eof() {
                         self.reconsume = true;
                         self.state = State::AfterAttributeName;
                         continue;
@@ -281,7 +905,7 @@ impl Iterator for HtmlTokenizer {
                 }
                 State::AfterAttributeName => {
                     // 空文字は無視する
-                    if c == ' ' {
+                    if is_html_whitespace(c) {
                         continue;
                     }
                     // SelfClosingStartTag状態に遷移する
@@ -310,7 +934,7 @@ impl Iterator for HtmlTokenizer {
                 }
                 State::BeforeAttributeValue => {
                     // 空白は無視
-                    if c ==' ' {
+                    if is_html_whitespace(c) {
                         continue;
                     }
                     // " 記号の場合、AttributeValueDoubleQuoted状態に遷移
@@ -333,10 +957,21 @@ impl Iterator for HtmlTokenizer {
                         self.state = State::AfterAttributeValueQuoted;
                         continue;
                     }
+                    // & 記号の場合、CharacterReference状態に遷移
+                    if c == '&' {
+                        self.return_state = State::AttributeValueDoubleQuoted;
+                        self.state = State::CharacterReference;
+                        continue;
+                    }
                     // 最後の文字の場合、Eofトークンを返す
                     if self.is_eof() {
                         return Some(HtmlToken::Eof);
                     }
+                    // NULはU+FFFDに置き換える
+                    if c == '\0' {
+                        self.append_attribute('\u{FFFD}', false);
+                        continue;
+                    }
                     // それ以外の場合、Attributesに文字を追加する
                     self.append_attribute(c, false);
                 }
@@ -346,30 +981,52 @@ impl Iterator for HtmlTokenizer {
                         self.state = State::AfterAttributeValueQuoted;
                         continue;
                     }
+                    // & 記号の場合、CharacterReference状態に遷移
+                    if c == '&' {
+                        self.return_state = State::AttributeValueSingleQuoted;
+                        self.state = State::CharacterReference;
+                        continue;
+                    }
                     // 最後の文字の場合、Eofトークンを返す
                     if self.is_eof() {
                         return Some(HtmlToken::Eof);
                     }
+                    // NULはU+FFFDに置き換える
+                    if c == '\0' {
+                        self.append_attribute('\u{FFFD}', false);
+                        continue;
+                    }
                     // それ以外の場合、Attributeに文字を追加する
                     self.append_attribute(c, false);
                 }
                 State::AttributeValueUnquoted => {
-                    if c == ' ' {
+                    if is_html_whitespace(c) {
                         self.state = State::BeforeAttributeName;
                         continue;
                     }
+                    if c == '&' {
+                        self.return_state = State::AttributeValueUnquoted;
+                        self.state = State::CharacterReference;
+                        continue;
+                    }
                     if c == '>' {
                         self.state = State::Data;
                         return self.take_latest_token();
                     }
+                    // 最後の文字の場合、作成中のタグを返し、次の呼び出しでEofトークンを返す
                     if self.is_eof() {
-                        return Some(HtmlToken::Eof);
+                        return self.take_latest_token_at_eof();
+                    }
+                    // NULはU+FFFDに置き換える
+                    if c == '\0' {
+                        self.append_attribute('\u{FFFD}', false);
+                        continue;
                     }
                     // それ以外の場合、Attributeに文字を追加する
                     self.append_attribute(c, false);
                 }
                 State::AfterAttributeValueQuoted => {
-                    if c == ' ' {
+                    if is_html_whitespace(c) {
                         self.state = State::BeforeAttributeName;
                         continue;
                     }
@@ -381,8 +1038,9 @@ impl Iterator for HtmlTokenizer {
                         self.state = State::Data;
                         return self.take_latest_token();
                     }
+                    // 属性値の閉じ引用符の後で入力が終わった場合も、作成中のタグを返してからEofを返す
                     if self.is_eof() {
-                        return Some(HtmlToken::Eof);
+                        return self.take_latest_token_at_eof();
                     }
                     self.reconsume = true;
                     self.state = State::BeforeAttributeValue;
@@ -397,6 +1055,69 @@ impl Iterator for HtmlTokenizer {
                         return Some(HtmlToken::Eof);
                     }
                 }
+                State::CharacterReference => {
+                    // & の次の文字から文字参照を解釈する
+                    let start = self.prev_pos;
+                    // 文字参照をデコードしない場合は、文字参照ではない場合と同じく & をそのまま扱う
+                    let reference = match self.decode_entities {
+                        true => parse_character_reference(self.input.get(start..).unwrap_or("")),
+                        false => None,
+                    };
+                    let is_data = matches!(self.return_state, State::Data | State::Rcdata);
+                    self.state = self.return_state.clone();
+                    match reference {
+                        // 文字参照の場合、参照の末尾まで読み進め、表す文字を返すか属性値に追加する
+                        Some((decoded, len)) => {
+                            self.pos = start + len;
+                            if is_data {
+                                return Some(HtmlToken::Char(decoded));
+                            }
+                            self.append_attribute_at(decoded, false, start - 1..self.pos);
+                        }
+                        // 文字参照ではない場合、& をそのまま扱い、次の文字を元の状態でもう一度判定する
+                        None => {
+                            self.reconsume = true;
+                            if is_data {
+                                return Some(HtmlToken::Char('&'));
+                            }
+                            self.append_attribute_at('&', false, start - 1..start);
+                        }
+                    }
+                }
+                State::Rcdata => {
+                    if c == '<' {
+                        self.state = State::ScriptDataLessThanSign;
+                        continue;
+                    }
+                    // RCDATAでは文字参照をデコードする
+                    if c == '&' {
+                        self.return_state = State::Rcdata;
+                        self.state = State::CharacterReference;
+                        continue;
+                    }
+                    if self.is_eof() {
+                        return Some(HtmlToken::Eof);
+                    }
+                    // NULはU+FFFDに置き換える
+                    if c == '\0' {
+                        return Some(HtmlToken::Char('\u{FFFD}'));
+                    }
+                    return Some(HtmlToken::Char(c));
+                }
+                State::RawText => {
+                    if c == '<' {
+                        self.state = State::ScriptDataLessThanSign;
+                        continue;
+                    }
+                    if self.is_eof() {
+                        return Some(HtmlToken::Eof);
+                    }
+                    // NULはU+FFFDに置き換える
+                    if c == '\0' {
+                        return Some(HtmlToken::Char('\u{FFFD}'));
+                    }
+                    return Some(HtmlToken::Char(c));
+                }
                 State::ScriptData => {
                     if c == '<' {
                         self.state = State::ScriptDataLessThanSign;
@@ -405,6 +1126,10 @@ impl Iterator for HtmlTokenizer {
                     if self.is_eof() {
                         return Some(HtmlToken::Eof);
                     }
+                    // NULはU+FFFDに置き換える
+                    if c == '\0' {
+                        return Some(HtmlToken::Char('\u{FFFD}'));
+                    }
                     // それ以外の場合
                     return Some(HtmlToken::Char(c));
                 }
@@ -417,7 +1142,7 @@ impl Iterator for HtmlTokenizer {
                     }
                     // それ以外の場合
                     self.reconsume = true;
-                    self.state = State::ScriptData;
+                    self.state = self.current_raw_text_state();
                     return Some(HtmlToken::Char('<'));
                 }
                 State::ScriptDataEndTagOpen => {
@@ -429,12 +1154,14 @@ impl Iterator for HtmlTokenizer {
                     }
                     // それ以外の場合
                     self.reconsume = true;
-                    self.state = State::ScriptData;
+                    self.state = self.current_raw_text_state();
                     return Some(HtmlToken::Char('<'));  // 使用では < と / の2つのトークンを返すようになっているが、1トークンしか返せないため < のみを返す
                 }
                 State::ScriptDataEndTagName => {
-                    if c == '>' {
+                    // 開始タグと同じ名前の終了タグの場合のみ、Data状態に遷移し終了タグを返す
+                    if c == '>' && self.raw_text_element.as_deref() == Some(self.buf.to_ascii_lowercase().as_str()) {
                         self.state = State::Data;
+                        self.raw_text_element = None;
                         return self.take_latest_token();
                     }
                     if c.is_ascii_alphabetic() {
@@ -444,13 +1171,16 @@ impl Iterator for HtmlTokenizer {
                     }
                     self.state = State::TemporaryBuffer;
                     self.buf = String::from("</") + &self.buf;
-                    self.buf.push(c);
+                    // 最後の文字の場合、EOFを表す文字はバッファに含めない
+                    if !self.is_eof() {
+                        self.buf.push(c);
+                    }
                     continue;
                 }
                 State::TemporaryBuffer => {
                     self.reconsume = true;
                     if self.buf.chars().count() == 0 {
-                        self.state = State::ScriptData;
+                        self.state = self.current_raw_text_state();
                         continue;
                     }
                     let c = self.buf.chars().nth(0).expect("self.buf should have at least 1 char");
@@ -462,6 +1192,38 @@ impl Iterator for HtmlTokenizer {
     }
 }
 
+impl Iterator for HtmlTokenizer<'_> {
+    type Item = HtmlToken;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // collect_textで読み戻したトークンがあれば先に返す
+        if let Some(t) = self.pending_token.take() {
+            return Some(t);
+        }
+        if self.truncated {
+            return None;
+        }
+        // トークン数の上限に達した場合、後続のトークンがあれば打ち切りを記録してNoneを返す
+        // 残りがEofのみの場合は読み捨てたトークンはないため、打ち切りとはせずにEofを返す
+        if let Some(max) = self.max_tokens {
+            if self.token_count >= max {
+                match self.next_token() {
+                    Some(HtmlToken::Eof) => return Some(HtmlToken::Eof),
+                    Some(_) => self.truncated = true,
+                    None => {}
+                }
+                return None;
+            }
+        }
+
+        let token = self.next_token();
+        if token.is_some() {
+            self.token_count += 1;
+        }
+        return token;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -528,6 +1290,47 @@ mod tests {
         }
     }
 
+    // 属性がタブや改行で区切られている場合
+    #[test]
+    fn test_attributes_separated_by_whitespace() {
+        let html = "<input\ttype=\"text\"\nname=\"q\">".to_string();
+        let mut tokenizer = HtmlTokenizer::new(html);
+
+        let mut attr1 = Attribute::new();
+        for c in "type".chars() {
+            attr1.add_char(c, true);
+        }
+        for c in "text".chars() {
+            attr1.add_char(c, false);
+        }
+        let mut attr2 = Attribute::new();
+        attr2.add_char('n', true);
+        attr2.add_char('a', true);
+        attr2.add_char('m', true);
+        attr2.add_char('e', true);
+        attr2.add_char('q', false);
+
+        let expected = HtmlToken::StartTag { tag: "input".to_string(), self_closing: false, attributes: vec![attr1, attr2] };
+        assert_eq!(Some(expected), tokenizer.next());
+    }
+
+    // 改ページや連続する空白、引用符のない値の後の改行で区切られている場合(テキスト中の空白はそのまま文字になる)
+    #[test]
+    fn test_attributes_separated_by_other_whitespace() {
+        let html = "<a\x0C\r\n href=x\nid='y'\t> \t</a>".to_string();
+        let tokens: Vec<HtmlToken> = HtmlTokenizer::new(html).collect();
+        match &tokens[0] {
+            HtmlToken::StartTag { tag, attributes, .. } => {
+                assert_eq!(tag, "a");
+                let attributes: Vec<(String, String)> = attributes.iter().map(|a| (a.name(), a.value())).collect();
+                assert_eq!(attributes, vec![("href".to_string(), "x".to_string()), ("id".to_string(), "y".to_string())]);
+            }
+            t => panic!("unexpected token: {:?}", t),
+        }
+        assert_eq!(tokens[1], HtmlToken::Char(' '));
+        assert_eq!(tokens[2], HtmlToken::Char('\t'));
+    }
+
     // 空要素タグのテスト
     #[test]
     fn test_self_closing_tag() {
@@ -562,4 +1365,710 @@ mod tests {
             assert_eq!(Some(e), tokenizer.next());
         }
     }
+
+    // 1つのタグが持つ属性数の最大値のテスト
+    #[test]
+    fn test_max_attributes_on_tag() {
+        let html = "<p a=1 b=2 c=3 d=4 e=5></p><br x=1>".to_string();
+        let mut tokenizer = HtmlTokenizer::new(html);
+        assert_eq!(tokenizer.max_attributes_on_tag(), 0);
+
+        tokenizer.next();
+        assert_eq!(tokenizer.max_attributes_on_tag(), 5);
+
+        // 後続のタグの属性数は前のタグの属性数に加算されない
+        for _ in tokenizer.by_ref() {}
+        assert_eq!(tokenizer.max_attributes_on_tag(), 5);
+    }
+
+    // 開始タグを文字列に戻し、もう一度トークナイズすると同じトークンになるテスト
+    #[test]
+    fn test_reserialize() {
+        let token = HtmlTokenizer::new("<input type=\"text\" disabled />".to_string()).next().expect("should have a token");
+        assert_eq!(token.reserialize(), "<input type=\"text\" disabled />");
+        let retokenized = HtmlTokenizer::new(token.reserialize()).next().expect("should have a token");
+        assert_eq!(retokenized, token);
+
+        // 値の中の " はエスケープする
+        let token = HtmlTokenizer::new("<a title='say \"hi\"'>".to_string()).next().expect("should have a token");
+        assert_eq!(token.reserialize(), "<a title=\"say &quot;hi&quot;\">");
+        assert_eq!(HtmlToken::EndTag { tag: "a".to_string() }.reserialize(), "</a>");
+    }
+
+    // 属性値の中の &quot; をデコードし、reserializeでエスケープし直すテスト
+    #[test]
+    fn test_quot_in_attribute_round_trip() {
+        let token = HtmlTokenizer::new("<a title=\"say &quot;hi&quot;\">".to_string()).next().expect("should have a token");
+        match &token {
+            HtmlToken::StartTag { attributes, .. } => assert_eq!(attributes[0].value(), "say \"hi\"".to_string()),
+            t => panic!("unexpected token: {:?}", t),
+        }
+        assert_eq!(token.reserialize(), "<a title=\"say &quot;hi&quot;\">");
+    }
+
+    // 属性数の上限を超えた属性は読み捨て、parse errorを記録するテスト
+    #[test]
+    fn test_max_attributes_per_tag() {
+        let html = "<p a=1 b=2 c=3 d=4 e=5>x</p><br x=1 y=2>".to_string();
+        let mut tokenizer = HtmlTokenizer::new(html);
+        tokenizer.set_max_attributes_per_tag(2);
+        let tokens: Vec<HtmlToken> = tokenizer.by_ref().collect();
+
+        match &tokens[0] {
+            HtmlToken::StartTag { tag, attributes, .. } => {
+                assert_eq!(tag, "p");
+                let names: Vec<String> = attributes.iter().map(|a| a.name()).collect();
+                assert_eq!(names, vec!["a".to_string(), "b".to_string()]);
+                assert_eq!(attributes[1].value(), "2".to_string());
+            }
+            t => panic!("unexpected token: {:?}", t),
+        }
+        assert_eq!(tokens[1], HtmlToken::Char('x'));
+        assert!(matches!(&tokens[3], HtmlToken::StartTag { attributes, .. } if attributes.len() == 2));
+        assert_eq!(tokenizer.parse_errors(), vec!["<p> has more than 2 attributes".to_string()]);
+    }
+
+    // 同じ名前の属性は上限に数えず、後に続く別の名前の属性を残すテスト
+    #[test]
+    fn test_max_attributes_per_tag_with_duplicates() {
+        let mut tokenizer = HtmlTokenizer::new("<a x=1 x=2 x=3 X=4 y=5 z=6>".to_string());
+        tokenizer.set_max_attributes_per_tag(2);
+        match tokenizer.next() {
+            Some(HtmlToken::StartTag { attributes, .. }) => {
+                let pairs: Vec<(String, String)> = attributes.iter().map(|a| (a.name(), a.value())).collect();
+                assert_eq!(pairs, vec![("x".to_string(), "1".to_string()), ("y".to_string(), "5".to_string())]);
+            }
+            t => panic!("unexpected token: {:?}", t),
+        }
+        assert_eq!(tokenizer.parse_errors(), vec!["<a> has more than 2 attributes".to_string()]);
+    }
+
+    // トークン数の上限を超えた場合に打ち切られるテスト
+    #[test]
+    fn test_max_tokens() {
+        let html = "<p>abcdef</p>".to_string();
+        let mut tokenizer = HtmlTokenizer::new(html);
+        tokenizer.set_max_tokens(3);
+        let expected = [
+            HtmlToken::StartTag { tag: "p".to_string(), self_closing: false, attributes: Vec::new() },
+            HtmlToken::Char('a'),
+            HtmlToken::Char('b'),
+        ];
+        for e in expected {
+            assert_eq!(Some(e), tokenizer.next());
+        }
+        assert!(!tokenizer.is_truncated());
+        assert!(tokenizer.next().is_none());
+        assert!(tokenizer.is_truncated());
+        assert!(tokenizer.next().is_none());
+    }
+
+    // トークン数が上限以内の場合は打ち切られないテスト
+    #[test]
+    fn test_max_tokens_not_reached() {
+        let html = "<p></p>".to_string();
+        let mut tokenizer = HtmlTokenizer::new(html);
+        tokenizer.set_max_tokens(3);
+        assert_eq!(tokenizer.by_ref().count(), 3);
+        assert!(!tokenizer.is_truncated());
+    }
+
+    // Eof以外のトークン数が上限とちょうど同じ場合は打ち切られないテスト
+    #[test]
+    fn test_max_tokens_exactly_reached() {
+        let html = "<p>a</p>".to_string();
+        let mut tokenizer = HtmlTokenizer::new(html);
+        tokenizer.set_max_tokens(3);
+        let tokens: Vec<HtmlToken> = tokenizer.by_ref().collect();
+        assert_eq!(tokens.len(), 4);
+        assert_eq!(tokens.last(), Some(&HtmlToken::Eof));
+        assert!(!tokenizer.is_truncated());
+    }
+
+    // 属性を持つ終了タグの場合、属性は読み捨てられる
+    #[test]
+    fn test_end_tag_with_attributes() {
+        let html = "<a></a class=\"x\" id=y></a b=\"x\"/>".to_string();
+        let mut tokenizer = HtmlTokenizer::new(html);
+        let expected = [
+            HtmlToken::StartTag { tag: "a".to_string(), self_closing: false, attributes: Vec::new() },
+            HtmlToken::EndTag { tag: "a".to_string() },
+            HtmlToken::EndTag { tag: "a".to_string() },
+            HtmlToken::Eof,
+        ];
+        for e in expected {
+            assert_eq!(Some(e), tokenizer.next());
+        }
+    }
+
+    // 連続した文字を文字列として読み取るテスト
+    #[test]
+    fn test_collect_text() {
+        let html = "hello<br>".to_string();
+        let mut tokenizer = HtmlTokenizer::new(html);
+        assert_eq!(tokenizer.collect_text(), "hello".to_string());
+        assert_eq!(
+            Some(HtmlToken::StartTag { tag: "br".to_string(), self_closing: false, attributes: Vec::new() }),
+            tokenizer.next()
+        );
+    }
+
+    // 同じ種類のタグかどうかを判定するテスト
+    #[test]
+    fn test_same_tag_kind() {
+        let mut attr = Attribute::new();
+        attr.add_char('a', true);
+        let div1 = HtmlToken::StartTag { tag: "div".to_string(), self_closing: false, attributes: Vec::new() };
+        let div2 = HtmlToken::StartTag { tag: "div".to_string(), self_closing: true, attributes: vec![attr] };
+        let div_end = HtmlToken::EndTag { tag: "div".to_string() };
+        let span = HtmlToken::StartTag { tag: "span".to_string(), self_closing: false, attributes: Vec::new() };
+
+        assert!(div1.same_tag_kind(&div2));
+        assert!(div_end.same_tag_kind(&HtmlToken::EndTag { tag: "div".to_string() }));
+        assert!(!div1.same_tag_kind(&div_end));
+        assert!(!div1.same_tag_kind(&span));
+        assert!(!HtmlToken::Char('a').same_tag_kind(&HtmlToken::Char('a')));
+    }
+
+    // 指定した開始タグまで読み飛ばすテスト
+    #[test]
+    fn test_skip_to_start_tag() {
+        let html = "<html><head><meta charset=utf-8><title>t</title></head></html>".to_string();
+        let mut tokenizer = HtmlTokenizer::new(html);
+        assert_eq!(
+            Some(HtmlToken::StartTag { tag: "title".to_string(), self_closing: false, attributes: Vec::new() }),
+            tokenizer.skip_to_start_tag("TITLE")
+        );
+        assert_eq!(Some(HtmlToken::Char('t')), tokenizer.next());
+        assert_eq!(None, tokenizer.skip_to_start_tag("body"));
+    }
+
+    // 引用符のない属性値の途中で入力が終わる場合
+    #[test]
+    fn test_unquoted_attribute_value_eof() {
+        let html = "<input value=abc".to_string();
+        let mut tokenizer = HtmlTokenizer::new(html);
+
+        let mut attr = Attribute::new();
+        attr.add_char('v', true);
+        attr.add_char('a', true);
+        attr.add_char('l', true);
+        attr.add_char('u', true);
+        attr.add_char('e', true);
+        attr.add_char('a', false);
+        attr.add_char('b', false);
+        attr.add_char('c', false);
+
+        let expected = [
+            HtmlToken::StartTag { tag: "input".to_string(), self_closing: false, attributes: vec![attr] },
+            HtmlToken::Eof,
+        ];
+        for e in expected {
+            assert_eq!(Some(e), tokenizer.next());
+        }
+        assert!(tokenizer.next().is_none());
+    }
+
+    // 引用符で囲まれた属性値の後で > がないまま入力が終わる場合
+    #[test]
+    fn test_quoted_attribute_value_eof() {
+        let html = "<a href=\"x\"".to_string();
+        let mut tokenizer = HtmlTokenizer::new(html);
+
+        let mut attr = Attribute::new();
+        for c in "href".chars() {
+            attr.add_char(c, true);
+        }
+        attr.add_char('x', false);
+
+        let expected = [
+            HtmlToken::StartTag { tag: "a".to_string(), self_closing: false, attributes: vec![attr] },
+            HtmlToken::Eof,
+        ];
+        for e in expected {
+            assert_eq!(Some(e), tokenizer.next());
+        }
+        assert!(tokenizer.next().is_none());
+    }
+
+    // トークナイザを最後まで読み進めてもパニックしないことを確認する
+    fn assert_no_panic(input: &str) {
+        let tokenizer = HtmlTokenizer::new(input.to_string());
+        for _ in tokenizer {}
+    }
+
+    // 不正な入力でパニックしないことを確認するテスト
+    #[test]
+    fn test_no_panic_on_adversarial_input() {
+        let huge_attribute = "<a title=\"".to_string() + &"x".repeat(10000) + "\">";
+        let nested_entities = "&amp;".repeat(100) + "&&&#;&#x;&#xZZ;&";
+        let inputs = [
+            "<",
+            "</",
+            "<!",
+            "<!-",
+            "</>",
+            "<a",
+            "<a ",
+            "<a b",
+            "<a b=",
+            "<a b='x",
+            "<a b=\"x",
+            "<a b=\"x\"",
+            "<a/",
+            "</a",
+            "</a b='x'",
+            "</a/>",
+            "</a b/>",
+            "<<<<>>>>",
+            "<script>",
+            "<script></",
+            "<script></scr",
+            "\u{0}<a\u{0}>\u{0}",
+            "あいう<あ>え</お>",
+            &huge_attribute,
+            &nested_entities,
+        ];
+        for input in inputs {
+            assert_no_panic(input);
+        }
+    }
+
+    // & を含む引用符のない属性値の途中で入力が終わる場合
+    #[test]
+    fn test_unquoted_attribute_value_with_ampersand_eof() {
+        let html = "<a href=a&amp".to_string();
+        let mut tokenizer = HtmlTokenizer::new(html);
+
+        let mut attr = Attribute::new();
+        for c in "href".chars() {
+            attr.add_char(c, true);
+        }
+        for c in "a&amp".chars() {
+            attr.add_char(c, false);
+        }
+
+        let expected = [
+            HtmlToken::StartTag { tag: "a".to_string(), self_closing: false, attributes: vec![attr] },
+            HtmlToken::Eof,
+        ];
+        for e in expected {
+            assert_eq!(Some(e), tokenizer.next());
+        }
+        assert!(tokenizer.next().is_none());
+    }
+
+    // script要素の中にいる間だけ要素名を返すテスト
+    #[test]
+    fn test_raw_text_context() {
+        let html = "<script>a</script><p>".to_string();
+        let mut tokenizer = HtmlTokenizer::new(html);
+        assert_eq!(tokenizer.raw_text_context(), None);
+
+        tokenizer.next();
+        assert_eq!(tokenizer.raw_text_context(), Some("script"));
+
+        assert_eq!(Some(HtmlToken::Char('a')), tokenizer.next());
+        assert_eq!(Some(HtmlToken::EndTag { tag: "script".to_string() }), tokenizer.next());
+        assert_eq!(tokenizer.raw_text_context(), None);
+    }
+
+    // script要素の中の別の終了タグは文字として扱うテスト
+    #[test]
+    fn test_script_with_other_end_tag() {
+        let html = "<script>a</b>c</script>".to_string();
+        let tokenizer = HtmlTokenizer::new(html);
+        let tokens: Vec<HtmlToken> = tokenizer.collect();
+        let mut expected = vec![
+            HtmlToken::StartTag { tag: "script".to_string(), self_closing: false, attributes: Vec::new() },
+        ];
+        for c in "a</b>c".chars() {
+            expected.push(HtmlToken::Char(c));
+        }
+        expected.push(HtmlToken::EndTag { tag: "script".to_string() });
+        expected.push(HtmlToken::Eof);
+        assert_eq!(tokens, expected);
+    }
+
+    // 属性の間に余分な = がある場合、= から始まる名前の属性として扱うテスト
+    #[test]
+    fn test_stray_equals_before_attribute_name() {
+        let html = "<div class=\"x\" = y>".to_string();
+        let mut tokenizer = HtmlTokenizer::new(html);
+        match tokenizer.next() {
+            Some(HtmlToken::StartTag { tag, attributes, .. }) => {
+                assert_eq!(tag, "div");
+                let names: Vec<String> = attributes.iter().map(|a| a.name()).collect();
+                assert_eq!(names, vec!["class".to_string(), "=".to_string(), "y".to_string()]);
+                assert_eq!(attributes[0].value(), "x");
+                assert_eq!(attributes[1].value(), "");
+                assert_eq!(attributes[2].value(), "");
+            }
+            t => panic!("unexpected token: {:?}", t),
+        }
+        assert_eq!(Some(HtmlToken::Eof), tokenizer.next());
+    }
+
+    // - を含むカスタム要素のタグ名と属性名がそのまま残るテスト
+    #[test]
+    fn test_custom_element() {
+        let html = "<my-widget data-x=\"1\"></my-widget>".to_string();
+        let mut tokenizer = HtmlTokenizer::new(html);
+        let mut attr = Attribute::new();
+        for c in "data-x".chars() {
+            attr.add_char(c, true);
+        }
+        attr.add_char('1', false);
+        let expected = [
+            HtmlToken::StartTag { tag: "my-widget".to_string(), self_closing: false, attributes: vec![attr] },
+            HtmlToken::EndTag { tag: "my-widget".to_string() },
+            HtmlToken::Eof,
+        ];
+        for e in expected {
+            assert_eq!(Some(e), tokenizer.next());
+        }
+    }
+
+    // 大文字小文字の異なるDOCTYPE宣言がDoctypeトークンになるテスト
+    #[test]
+    fn test_doctype() {
+        for html in ["<!DOCTYPE html><p>", "<!doctype HTML><p>"] {
+            let mut tokenizer = HtmlTokenizer::new(html.to_string());
+            let expected = [
+                HtmlToken::Doctype { name: Some("html".to_string()) },
+                HtmlToken::StartTag { tag: "p".to_string(), self_closing: false, attributes: Vec::new() },
+                HtmlToken::Eof,
+            ];
+            for e in expected {
+                assert_eq!(Some(e), tokenizer.next());
+            }
+        }
+    }
+
+    // 識別子を持つDOCTYPE宣言や名前のないDOCTYPE宣言のテスト
+    #[test]
+    fn test_doctype_with_identifiers_and_without_name() {
+        let html = "<!DOCTYPE html PUBLIC \"-//W3C//DTD HTML 4.01//EN\"><!DOCTYPE>".to_string();
+        let tokens: Vec<HtmlToken> = HtmlTokenizer::new(html).collect();
+        let expected = vec![
+            HtmlToken::Doctype { name: Some("html".to_string()) },
+            HtmlToken::Doctype { name: None },
+            HtmlToken::Eof,
+        ];
+        assert_eq!(tokens, expected);
+    }
+
+    // DOCTYPE宣言の途中で入力が終わる場合
+    #[test]
+    fn test_doctype_eof() {
+        let tokens: Vec<HtmlToken> = HtmlTokenizer::new("<!DOCTYPE ht".to_string()).collect();
+        assert_eq!(tokens, vec![HtmlToken::Doctype { name: Some("ht".to_string()) }, HtmlToken::Eof]);
+    }
+
+    // コメントが1つのCommentトークンになるテスト
+    #[test]
+    fn test_comment() {
+        let tokens: Vec<HtmlToken> = HtmlTokenizer::new("<!-- hello -->".to_string()).collect();
+        assert_eq!(tokens, vec![HtmlToken::Comment(" hello ".to_string()), HtmlToken::Eof]);
+    }
+
+    // 文字の間にあり、閉じる --> 以外の - を含むコメントのテスト
+    #[test]
+    fn test_comment_between_text() {
+        let tokens: Vec<HtmlToken> = HtmlTokenizer::new("a<!-- x - y -- z -->b".to_string()).collect();
+        let expected = vec![
+            HtmlToken::Char('a'),
+            HtmlToken::Comment(" x - y -- z ".to_string()),
+            HtmlToken::Char('b'),
+            HtmlToken::Eof,
+        ];
+        assert_eq!(tokens, expected);
+    }
+
+    // 空のコメントや閉じられていないコメント、不正なコメントのテスト
+    #[test]
+    fn test_comment_edge_cases() {
+        let tokens: Vec<HtmlToken> = HtmlTokenizer::new("<!--><!---><!---->".to_string()).collect();
+        let expected = vec![
+            HtmlToken::Comment("".to_string()),
+            HtmlToken::Comment("".to_string()),
+            HtmlToken::Comment("".to_string()),
+            HtmlToken::Eof,
+        ];
+        assert_eq!(tokens, expected);
+
+        let tokens: Vec<HtmlToken> = HtmlTokenizer::new("<!-- abc -".to_string()).collect();
+        assert_eq!(tokens, vec![HtmlToken::Comment(" abc ".to_string()), HtmlToken::Eof]);
+
+        let tokens: Vec<HtmlToken> = HtmlTokenizer::new("<![CDATA[x]]>".to_string()).collect();
+        assert_eq!(tokens, vec![HtmlToken::Comment("[CDATA[x]]".to_string()), HtmlToken::Eof]);
+    }
+
+    // 文字列からCharトークンの文字のみを取り出す
+    fn chars_of(html: &str) -> String {
+        return HtmlTokenizer::new(html.to_string())
+            .filter_map(|t| match t {
+                HtmlToken::Char(c) => Some(c),
+                _ => None,
+            })
+            .collect();
+    }
+
+    // 名前付き文字参照、10進数と16進数の数値文字参照をデコードするテスト
+    #[test]
+    fn test_character_references() {
+        assert_eq!(chars_of("Tom &amp; Jerry"), "Tom & Jerry".to_string());
+        assert_eq!(chars_of("&lt;&gt;&quot;&apos;&nbsp;"), "<>\"'\u{A0}".to_string());
+        assert_eq!(chars_of("&#123;&#x1F4A9;&#X41"), "{\u{1F4A9}A".to_string());
+        assert_eq!(chars_of("&#0;&#x110000;"), "\u{FFFD}\u{FFFD}".to_string());
+    }
+
+    // 不正な文字参照はそのまま残すテスト
+    #[test]
+    fn test_malformed_character_references() {
+        assert_eq!(chars_of("&nope; &amp &# &x &"), "&nope; &amp &# &x &".to_string());
+        assert_eq!(chars_of("&&lt;"), "&<".to_string());
+    }
+
+    // 属性値の中の文字参照をデコードするテスト
+    #[test]
+    fn test_character_references_in_attribute() {
+        let html = "<a title=\"a&amp;b\" alt='&lt;&x' href=?q=1&amp;r=&#50;>".to_string();
+        let mut tokenizer = HtmlTokenizer::new(html);
+        match tokenizer.next() {
+            Some(HtmlToken::StartTag { attributes, .. }) => {
+                let values: Vec<String> = attributes.iter().map(|a| a.value()).collect();
+                assert_eq!(values, vec!["a&b".to_string(), "<&x".to_string(), "?q=1&r=2".to_string()]);
+            }
+            t => panic!("unexpected token: {:?}", t),
+        }
+    }
+
+    // 文字参照をデコードしない設定の場合、元の文字のまま出力するテスト
+    #[test]
+    fn test_preserve_character_references() {
+        assert_eq!(chars_of("a&amp;b"), "a&b".to_string());
+
+        let mut tokenizer = HtmlTokenizer::new("a&amp;b<a title=\"&lt;\">".to_string());
+        tokenizer.set_decode_entities(false);
+        let tokens: Vec<HtmlToken> = tokenizer.collect();
+        let text: String = tokens
+            .iter()
+            .filter_map(|t| match t {
+                HtmlToken::Char(c) => Some(*c),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(text, "a&amp;b".to_string());
+        match &tokens[7] {
+            HtmlToken::StartTag { attributes, .. } => assert_eq!(attributes[0].value(), "&lt;".to_string()),
+            t => panic!("unexpected token: {:?}", t),
+        }
+    }
+
+    // take_latest_tokenが作成したタグを返し、リセット後のassertが発火しないことを確認するテスト
+    #[test]
+    fn test_take_latest_token_returns_tag() {
+        let tokens: Vec<HtmlToken> = HtmlTokenizer::new("<p></p>".to_string()).collect();
+        let expected = vec![
+            HtmlToken::StartTag { tag: "p".to_string(), self_closing: false, attributes: Vec::new() },
+            HtmlToken::EndTag { tag: "p".to_string() },
+            HtmlToken::Eof,
+        ];
+        assert_eq!(tokens, expected);
+    }
+
+    // 空でない入力の最後のトークンは必ずEofで、Eofは1度だけ返されるテスト
+    #[test]
+    fn test_eof_emitted_once_at_end() {
+        let inputs = [
+            "a", "<", "<p", "<p>", "</p>", "<p class=x", "<p class=\"x\">t", "<!-- c", "<!DOCTYPE", "&", "<script>x",
+        ];
+        for input in inputs {
+            let tokens: Vec<HtmlToken> = HtmlTokenizer::new(input.to_string()).collect();
+            assert_eq!(tokens.last(), Some(&HtmlToken::Eof), "input: {}", input);
+            assert_eq!(tokens.iter().filter(|t| **t == HtmlToken::Eof).count(), 1, "input: {}", input);
+        }
+    }
+
+    // 属性が10000個あるタグでも、状態遷移の回数が入力の長さに比例するテスト
+    // (take_latest_tokenでタグをcloneしなくなったため、属性の追加と取り出しはどちらも属性数に比例する)
+    // 手元の計測では、10000個の属性を持つタグの読み取りはdebugビルドで約30ms、releaseビルドで約10msだった
+    #[test]
+    fn test_many_attributes_performance() {
+        let mut html = "<div".to_string();
+        for i in 0..10000 {
+            html.push_str(&alloc::format!(" a{}=\"{}\"", i, i));
+        }
+        html.push_str("></div>");
+        let length = html.chars().count();
+
+        let mut tokenizer = HtmlTokenizer::new(html);
+        match tokenizer.next() {
+            Some(HtmlToken::StartTag { attributes, .. }) => assert_eq!(attributes.len(), 10000),
+            t => panic!("unexpected token: {:?}", t),
+        }
+        assert_eq!(tokenizer.max_attributes_on_tag(), 10000);
+        // 1文字につき、読み直し(reconsume)を含めて高々2回の状態遷移で読み終える
+        assert!(tokenizer.steps <= 2 * length, "steps: {}, length: {}", tokenizer.steps, length);
+    }
+
+    // RCDATA要素の中のタグは文字として扱い、文字参照はデコードするテスト
+    #[test]
+    fn test_rcdata_title() {
+        let tokens: Vec<HtmlToken> = HtmlTokenizer::new("<title>a<b>c&amp;</title><b>".to_string()).collect();
+        let mut expected = vec![
+            HtmlToken::StartTag { tag: "title".to_string(), self_closing: false, attributes: Vec::new() },
+        ];
+        for c in "a<b>c&".chars() {
+            expected.push(HtmlToken::Char(c));
+        }
+        expected.push(HtmlToken::EndTag { tag: "title".to_string() });
+        expected.push(HtmlToken::StartTag { tag: "b".to_string(), self_closing: false, attributes: Vec::new() });
+        expected.push(HtmlToken::Eof);
+        assert_eq!(tokens, expected);
+    }
+
+    // RAWTEXT要素の中では別の終了タグや文字参照をそのまま文字として扱うテスト
+    #[test]
+    fn test_rawtext_style() {
+        let tokens: Vec<HtmlToken> = HtmlTokenizer::new("<style>a</b>&amp;</STYLE>".to_string()).collect();
+        let mut expected = vec![
+            HtmlToken::StartTag { tag: "style".to_string(), self_closing: false, attributes: Vec::new() },
+        ];
+        for c in "a</b>&amp;".chars() {
+            expected.push(HtmlToken::Char(c));
+        }
+        expected.push(HtmlToken::EndTag { tag: "style".to_string() });
+        expected.push(HtmlToken::Eof);
+        assert_eq!(tokens, expected);
+    }
+
+    // 2行目にあるタグの終わりの位置を返すテスト
+    #[test]
+    fn test_current_position() {
+        let mut tokenizer = HtmlTokenizer::new("<p>a\n  <b>c".to_string());
+        assert_eq!(tokenizer.current_position(), Position { line: 1, column: 1 });
+
+        tokenizer.next();
+        assert_eq!(tokenizer.current_position(), Position { line: 1, column: 4 });
+
+        // 改行の直後は次の行の1列目
+        tokenizer.next();
+        tokenizer.next();
+        assert_eq!(tokenizer.current_position(), Position { line: 2, column: 1 });
+
+        assert!(tokenizer.skip_to_start_tag("b").is_some());
+        assert_eq!(tokenizer.current_position(), Position { line: 2, column: 6 });
+    }
+
+    // 属性の名前と値の位置を返すテスト
+    #[test]
+    fn test_next_with_attr_spans() {
+        let html = "<div class=\"x\">";
+        let mut tokenizer = HtmlTokenizer::from_str(html);
+        let (token, spans) = tokenizer.next_with_attr_spans().expect("should have a token");
+        assert!(matches!(token, HtmlToken::StartTag { .. }));
+        assert_eq!(spans, vec![AttributeSpan { name: 5..10, value: Some(12..13) }]);
+        assert_eq!(&html[spans[0].name.clone()], "class");
+        assert_eq!(&html[spans[0].value.clone().expect("should have a value")], "x");
+    }
+
+    // 値のない属性、文字参照を含む値、重複した属性の位置を返すテスト
+    #[test]
+    fn test_next_with_attr_spans_edge_cases() {
+        let html = "<p hidden a=&amp;b a=c>t";
+        let mut tokenizer = HtmlTokenizer::from_str(html);
+        let (_, spans) = tokenizer.next_with_attr_spans().expect("should have a token");
+        let expected = vec![
+            AttributeSpan { name: 3..9, value: None },
+            AttributeSpan { name: 10..11, value: Some(12..18) },
+        ];
+        assert_eq!(spans, expected);
+
+        let (token, spans) = tokenizer.next_with_attr_spans().expect("should have a token");
+        assert_eq!(token, HtmlToken::Char('t'));
+        assert!(spans.is_empty());
+    }
+
+    // 文字と属性値の中のNULをU+FFFDに置き換えるテスト
+    #[test]
+    fn test_nul_replacement() {
+        let tokens: Vec<HtmlToken> = HtmlTokenizer::new("\u{0}".to_string()).collect();
+        assert_eq!(tokens, vec![HtmlToken::Char('\u{FFFD}'), HtmlToken::Eof]);
+
+        let mut tokenizer = HtmlTokenizer::new("<a title=\"x\u{0}y\">".to_string());
+        match tokenizer.next() {
+            Some(HtmlToken::StartTag { attributes, .. }) => assert_eq!(attributes[0].value(), "x\u{FFFD}y"),
+            t => panic!("unexpected token: {:?}", t),
+        }
+    }
+
+    // from_strで作成した場合も、newで作成した場合と同じトークン列になるテスト
+    #[test]
+    fn test_from_str_same_tokens_as_new() {
+        let mut html = String::new();
+        while html.chars().count() < 100000 {
+            html.push_str("<div class=\"a&amp;b\" id=x>テキスト&lt;\u{0}</div><!-- c --><br/><script>a</b></script>\n");
+        }
+        let from_new: Vec<HtmlToken> = HtmlTokenizer::new(html.clone()).collect();
+        let from_str: Vec<HtmlToken> = HtmlTokenizer::from_str(&html).collect();
+        assert_eq!(from_new, from_str);
+        assert_eq!(from_str.last(), Some(&HtmlToken::Eof));
+    }
+
+    // 最後まで読んだ後に巻き戻すと、同じトークン列をもう一度読めるテスト
+    #[test]
+    fn test_rewind() {
+        let mut tokenizer = HtmlTokenizer::new("<title>a&amp;</title><p class=x>b</p>".to_string());
+        let first: Vec<HtmlToken> = tokenizer.by_ref().collect();
+        assert!(tokenizer.next().is_none());
+
+        tokenizer.rewind();
+        let second: Vec<HtmlToken> = tokenizer.by_ref().collect();
+        assert_eq!(first, second);
+
+        // 途中で巻き戻し、トークン数の上限を設定して読み直す
+        tokenizer.rewind();
+        tokenizer.next();
+        tokenizer.rewind();
+        tokenizer.set_max_tokens(1);
+        assert_eq!(tokenizer.next(), Some(first[0].clone()));
+        assert!(tokenizer.next().is_none());
+        assert!(tokenizer.is_truncated());
+    }
+
+    // 同じ名前の属性は最初の属性のみを残すテスト
+    #[test]
+    fn test_duplicate_attributes() {
+        let mut tokenizer = HtmlTokenizer::new("<div a=\"1\" A=\"2\" b=\"3\" c c=\"4\">".to_string());
+        match tokenizer.next() {
+            Some(HtmlToken::StartTag { attributes, .. }) => {
+                let pairs: Vec<(String, String)> = attributes.iter().map(|a| (a.name(), a.value())).collect();
+                let expected = vec![
+                    ("a".to_string(), "1".to_string()),
+                    ("b".to_string(), "3".to_string()),
+                    ("c".to_string(), "".to_string()),
+                ];
+                assert_eq!(pairs, expected);
+            }
+            t => panic!("unexpected token: {:?}", t),
+        }
+    }
+
+    // トークン列をHTMLの文字列に戻すテスト(属性値は " で囲む形に揃える)
+    #[test]
+    fn test_display_round_trip() {
+        let html = "<!doctype html><p class='a' title=\"x &quot;y&quot; &amp; z\">hi<br/></p><!-- c -->";
+        let serialized: String = HtmlTokenizer::from_str(html).map(|t| t.to_string()).collect();
+        assert_eq!(
+            serialized,
+            "<!DOCTYPE html><p class=\"a\" title=\"x &quot;y&quot; &amp; z\">hi<br /></p><!-- c -->".to_string()
+        );
+
+        // もう一度トークナイズすると同じトークン列になる
+        let original: Vec<HtmlToken> = HtmlTokenizer::from_str(html).collect();
+        let reparsed: Vec<HtmlToken> = HtmlTokenizer::from_str(&serialized).collect();
+        assert_eq!(original, reparsed);
+    }
 }